@@ -0,0 +1,252 @@
+//! Generates `include/roxy.h` from the items of `src/ffi.rs`.
+//!
+//! Only the shapes the C API uses are understood: `pub const`s, opaque
+//! `pub struct`s, `#[repr(C)]` enums and structs, `extern "C" fn` type
+//! aliases and `extern "C" fn` exports, with their `///` docs. Enum
+//! variants become `ROXY_<VARIANT>` constants.
+
+use std::{env, fs, path::Path};
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let root = env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+    let source = fs::read_to_string(Path::new(&root).join("src/ffi.rs")).expect("src/ffi.rs");
+    let header = header(&source);
+
+    let out = env::var("OUT_DIR").expect("set by cargo");
+    fs::write(Path::new(&out).join("roxy.h"), &header).expect("writing OUT_DIR/roxy.h");
+
+    // rewritten only on change, so an unchanged header keeps its mtime
+    let path = Path::new(&root).join("include/roxy.h");
+    if fs::read_to_string(&path).ok().as_deref() != Some(header.as_str()) {
+        fs::write(&path, &header).expect("writing include/roxy.h");
+    }
+}
+
+fn header(source: &str) -> String {
+    let mut out = String::from(
+        "/*
+ * C API of roxy, generated from src/ffi.rs by build.rs. Do not edit.
+ *
+ * Link against the cdylib built by `cargo build` (target/<profile>/libroxy.so
+ * on Linux).
+ */
+
+#ifndef ROXY_H
+#define ROXY_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+",
+    );
+
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut docs = Vec::new();
+    let mut repr_c = false;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        idx += 1;
+
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.strip_prefix(' ').unwrap_or(doc));
+            continue;
+        }
+        if line.starts_with("#[") {
+            repr_c |= line == "#[repr(C)]";
+            continue;
+        }
+
+        let item = if let Some(rest) = line.strip_prefix("pub const ") {
+            let (name, value) = rest.split_once(':').expect("typed const");
+            let value = value.split_once('=').expect("const value").1;
+            Some(format!(
+                "#define {name} {}\n",
+                value.trim().trim_end_matches(';')
+            ))
+        } else if let Some(rest) = line.strip_prefix("pub enum ") {
+            let name = rest.trim_end_matches(" {");
+            let body = block(&lines, &mut idx);
+            Some(enumeration(name, &body))
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.trim_end_matches(" {");
+            let body = block(&lines, &mut idx);
+            Some(match repr_c {
+                true => structure(name, &body),
+                false => format!("typedef struct {name} {name};\n"),
+            })
+        } else if let Some(rest) = line.strip_prefix("pub type ") {
+            let (name, _) = rest.split_once(" = ").expect("type alias");
+            let signature = signature(line, &lines, &mut idx);
+            let (params, ret) = parse_signature(&signature);
+            Some(format!("typedef {}(*{name})({params});\n", spaced(&ret)))
+        } else if line.starts_with("pub extern \"C\" fn ")
+            || line.starts_with("pub unsafe extern \"C\" fn ")
+        {
+            let name = line
+                .split("fn ")
+                .nth(1)
+                .and_then(|rest| rest.split('(').next());
+            let signature = signature(line, &lines, &mut idx);
+            let (params, ret) = parse_signature(&signature);
+            Some(format!(
+                "{}{}({params});\n",
+                spaced(&ret),
+                name.expect("fn name")
+            ))
+        } else {
+            None
+        };
+
+        if let Some(item) = item {
+            out.push('\n');
+            out.push_str(&comment(&docs, ""));
+            out.push_str(&item);
+        }
+        if !line.starts_with("#[") {
+            docs.clear();
+            repr_c = false;
+        }
+    }
+
+    out.push_str(
+        "
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ROXY_H */
+",
+    );
+    out
+}
+
+/// Lines up to the `}` closing the item that started on the line before
+/// `idx`, advancing `idx` past it.
+fn block<'a>(lines: &[&'a str], idx: &mut usize) -> Vec<&'a str> {
+    let start = *idx;
+    while lines[*idx] != "}" {
+        *idx += 1;
+    }
+    *idx += 1;
+    lines[start..*idx - 1].to_vec()
+}
+
+/// A function signature from `first` to its `{` or `;`, on one line.
+fn signature(first: &str, lines: &[&str], idx: &mut usize) -> String {
+    let mut signature = first.to_owned();
+    while !signature.ends_with('{') && !signature.ends_with(';') {
+        signature.push_str(lines[*idx].trim());
+        *idx += 1;
+    }
+    signature
+}
+
+/// C parameter list and return type of a Rust signature.
+fn parse_signature(signature: &str) -> (String, String) {
+    let open = signature.find('(').expect("parameter list");
+    let close = signature.rfind(')').expect("parameter list");
+
+    let params = signature[open + 1..close]
+        .split(',')
+        .map(str::trim)
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            let (name, ty) = param.split_once(':').expect("typed parameter");
+            format!("{}{}", spaced(&c_type(ty.trim())), name.trim())
+        })
+        .collect::<Vec<_>>();
+
+    let ret = signature[close + 1..]
+        .trim_end_matches(['{', ';'])
+        .trim()
+        .strip_prefix("->")
+        .map_or("void".to_owned(), |ty| c_type(ty.trim()));
+
+    match params.is_empty() {
+        true => ("void".to_owned(), ret),
+        false => (params.join(", "), ret),
+    }
+}
+
+fn enumeration(name: &str, body: &[&str]) -> String {
+    let mut out = format!("typedef enum {name} {{\n");
+    let mut docs = Vec::new();
+    for line in body.iter().map(|line| line.trim()) {
+        if let Some(doc) = line.strip_prefix("///") {
+            docs.push(doc.trim());
+        } else if let Some((variant, value)) = line.trim_end_matches(',').split_once(" = ") {
+            out.push_str(&comment(&docs, "    "));
+            out.push_str(&format!("    ROXY_{} = {value},\n", variant.to_uppercase()));
+            docs.clear();
+        }
+    }
+    out.push_str(&format!("}} {name};\n"));
+    out
+}
+
+fn structure(name: &str, body: &[&str]) -> String {
+    let mut out = format!("typedef struct {name} {{\n");
+    for line in body.iter().map(|line| line.trim()) {
+        if let Some((field, ty)) = line.strip_prefix("pub ").and_then(|f| f.split_once(':')) {
+            let ty = c_type(ty.trim().trim_end_matches(','));
+            out.push_str(&format!("    {}{field};\n", spaced(&ty)));
+        }
+    }
+    out.push_str(&format!("}} {name};\n"));
+    out
+}
+
+fn c_type(ty: &str) -> String {
+    if let Some(pointee) = ty.strip_prefix("*const ") {
+        return format!("const {} *", c_type(pointee));
+    }
+    if let Some(pointee) = ty.strip_prefix("*mut ") {
+        return format!("{} *", c_type(pointee));
+    }
+    match ty {
+        "c_int" => "int",
+        "c_char" => "char",
+        "c_void" => "void",
+        "bool" => "bool",
+        "i64" => "int64_t",
+        "f64" => "double",
+        "usize" => "size_t",
+        named if named.starts_with("Roxy") => named,
+        other => panic!("build.rs can not map `{other}` to C"),
+    }
+    .to_owned()
+}
+
+/// `ty` followed by a space unless it ends in `*`.
+fn spaced(ty: &str) -> String {
+    match ty.ends_with('*') {
+        true => ty.to_owned(),
+        false => format!("{ty} "),
+    }
+}
+
+fn comment(docs: &[&str], indent: &str) -> String {
+    match docs {
+        [] => String::new(),
+        [line] => format!("{indent}/* {line} */\n"),
+        lines => {
+            let mut out = format!("{indent}/*\n");
+            for line in lines {
+                match line.is_empty() {
+                    true => out.push_str(&format!("{indent} *\n")),
+                    false => out.push_str(&format!("{indent} * {line}\n")),
+                }
+            }
+            out.push_str(&format!("{indent} */\n"));
+            out
+        }
+    }
+}
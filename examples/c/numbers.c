@@ -0,0 +1,112 @@
+/*
+ * Runs a roxy script through the C API and prints its result.
+ *
+ *   cargo build
+ *   cc -Iinclude examples/c/numbers.c -Ltarget/debug -lroxy -o numbers
+ *   LD_LIBRARY_PATH=target/debug ./numbers programs/numbers.rx
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "roxy.h"
+
+static char *read_file(const char *path) {
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+        return NULL;
+    }
+
+    fseek(file, 0, SEEK_END);
+    long size = ftell(file);
+    rewind(file);
+
+    char *buf = malloc(size + 1);
+    if (buf != NULL) {
+        size_t read = fread(buf, 1, size, file);
+        buf[read] = '\0';
+    }
+    fclose(file);
+    return buf;
+}
+
+/* Native `scale(x)` multiplying by the factor passed as userdata. */
+static int scale(void *userdata, const RoxyValue *args, size_t argc,
+                 RoxyValue *out) {
+    double factor = *(double *)userdata;
+    (void)argc;
+
+    switch (args[0].ty) {
+    case ROXY_INT:
+        out->ty = ROXY_FLOAT;
+        out->number = (double)args[0].integer * factor;
+        return ROXY_OK;
+    case ROXY_FLOAT:
+        out->ty = ROXY_FLOAT;
+        out->number = args[0].number * factor;
+        return ROXY_OK;
+    default:
+        out->ty = ROXY_STRING;
+        out->string = "scale expects a number";
+        out->length = 22;
+        return ROXY_ERROR;
+    }
+}
+
+static void print_value(RoxyValue value) {
+    switch (value.ty) {
+    case ROXY_NIL:
+        printf("nil\n");
+        break;
+    case ROXY_BOOL:
+        printf("%s\n", value.boolean ? "true" : "false");
+        break;
+    case ROXY_INT:
+        printf("%lld\n", (long long)value.integer);
+        break;
+    case ROXY_FLOAT:
+        printf("%g\n", value.number);
+        break;
+    case ROXY_STRING:
+        printf("%.*s\n", (int)value.length, value.string);
+        break;
+    case ROXY_OBJECT:
+        printf("<object>\n");
+        break;
+    }
+}
+
+int main(int argc, char **argv) {
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s <file.rx>\n", argv[0]);
+        return 64;
+    }
+
+    char *source = read_file(argv[1]);
+    if (source == NULL) {
+        perror(argv[1]);
+        return 66;
+    }
+
+    RoxyVm *vm = roxy_vm_new();
+    static double factor = 2.0;
+    int status = roxy_register_native(vm, "scale", 1, scale, &factor);
+
+    RoxyValue result;
+    if (status == ROXY_OK) {
+        status = roxy_eval(vm, source, &result);
+    }
+    if (status == ROXY_OK) {
+        print_value(result);
+        status = roxy_eval(vm, "scale(21)", &result);
+    }
+    if (status == ROXY_OK) {
+        print_value(result);
+    } else {
+        fprintf(stderr, "%s\n", roxy_get_error(vm));
+    }
+
+    roxy_vm_free(vm);
+    free(source);
+    return status == ROXY_OK ? 0 : 70;
+}
@@ -0,0 +1,70 @@
+//! Runs independent scripts on a small pool of worker threads.
+//!
+//! Every worker owns one `Vm`, which is created on the main thread and moved
+//! into the worker. Results travel back as plain Rust values, and a value
+//! that should live on in another VM goes through `Vm::import`.
+
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use roxy::{convert::FromValue, vm::Vm};
+
+const WORKERS: usize = 4;
+
+fn main() {
+    let scripts = (1..=8)
+        .map(|n| {
+            format!(
+                "fn square(n) {{
+                    return n * n;
+                }}
+                square({n}) + offset()"
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let (jobs, queue) = mpsc::channel::<(usize, String)>();
+    let queue = Arc::new(Mutex::new(queue));
+    let (done, results) = mpsc::channel();
+
+    let workers = (0..WORKERS)
+        .map(|id| {
+            let mut vm = Vm::new();
+            vm.register_fn("offset", move || id as i64 * 100).unwrap();
+
+            let queue = Arc::clone(&queue);
+            let done = done.clone();
+            thread::spawn(move || loop {
+                let job = queue.lock().unwrap().recv();
+                let Ok((index, script)) = job else {
+                    break;
+                };
+
+                let result = vm.eval(&script).and_then(i64::from_value);
+                done.send((index, id, result.map_err(|e| e.to_string())))
+                    .unwrap();
+            })
+        })
+        .collect::<Vec<_>>();
+    drop(done);
+
+    for job in scripts.into_iter().enumerate() {
+        jobs.send(job).unwrap();
+    }
+    drop(jobs);
+
+    let mut results = results.iter().collect::<Vec<_>>();
+    results.sort_by_key(|(index, _, _)| *index);
+    for (index, worker, result) in results {
+        match result {
+            Ok(value) => println!("script {index} on worker {worker}: {value}"),
+            Err(e) => println!("script {index} on worker {worker} failed: {e}"),
+        }
+    }
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
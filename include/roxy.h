@@ -0,0 +1,110 @@
+/*
+ * C API of roxy, generated from src/ffi.rs by build.rs. Do not edit.
+ *
+ * Link against the cdylib built by `cargo build` (target/<profile>/libroxy.so
+ * on Linux).
+ */
+
+#ifndef ROXY_H
+#define ROXY_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ROXY_OK 0
+
+#define ROXY_ERROR -1
+
+/* A VM together with the last result and error it reported. */
+typedef struct RoxyVm RoxyVm;
+
+typedef enum RoxyType {
+    ROXY_NIL = 0,
+    ROXY_BOOL = 1,
+    ROXY_INT = 2,
+    ROXY_FLOAT = 3,
+    ROXY_STRING = 4,
+    /* Functions and host objects, which C can not look into. */
+    ROXY_OBJECT = 5,
+} RoxyType;
+
+/*
+ * A value crossing the C boundary.
+ *
+ * Only the field matching `ty` is meaningful. `string` is not NUL
+ * terminated; it points into the VM heap and stays valid until the next
+ * call on the VM, or for the duration of a native call for its arguments.
+ */
+typedef struct RoxyValue {
+    RoxyType ty;
+    bool boolean;
+    int64_t integer;
+    double number;
+    const char *string;
+    size_t length;
+} RoxyValue;
+
+/*
+ * Native function implemented in C.
+ *
+ * Returns `ROXY_OK` and stores the result in `out`, or anything else to
+ * raise a runtime error, optionally with a string `out` as its message.
+ */
+typedef int (*RoxyNativeFn)(void *userdata, const RoxyValue *args, size_t argc, RoxyValue *out);
+
+/* Creates a VM with the default limits. Never returns NULL. */
+RoxyVm *roxy_vm_new(void);
+
+/*
+ * Frees a VM created by `roxy_vm_new`. NULL is ignored.
+ *
+ * # Safety
+ *
+ * `vm` must come from `roxy_vm_new` and not be used afterwards.
+ */
+void roxy_vm_free(RoxyVm *vm);
+
+/*
+ * Runs the NUL terminated `source`, storing its result in `out` unless
+ * `out` is NULL.
+ *
+ * # Safety
+ *
+ * `vm` must be a live handle and `source` a valid C string.
+ */
+int roxy_eval(RoxyVm *vm, const char *source, RoxyValue *out);
+
+/*
+ * Last error reported by `vm`, or NULL if the last call succeeded.
+ *
+ * The string belongs to the VM and is valid until its next call.
+ *
+ * # Safety
+ *
+ * `vm` must be a live handle.
+ */
+const char *roxy_get_error(const RoxyVm *vm);
+
+/*
+ * Registers `function` as the global native `name`.
+ *
+ * A negative `arity` accepts any number of arguments. `userdata` is handed
+ * back to every call.
+ *
+ * # Safety
+ *
+ * `vm` must be a live handle, `name` a valid C string, and `userdata` must
+ * stay valid for as long as the VM lives, on whichever thread runs it.
+ */
+int roxy_register_native(RoxyVm *vm, const char *name, int arity, RoxyNativeFn function, void *userdata);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ROXY_H */
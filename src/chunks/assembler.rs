@@ -0,0 +1,510 @@
+use std::sync::Arc;
+
+use crate::{
+    compiler::{unescape, Span},
+    error::{Compile, ErrorKind, RxError},
+    object::{Function, Heap},
+    value::Value,
+    Res,
+};
+
+use super::{Chunk, Opcode};
+
+/// Builds a function from `.rxasm` text, the format `Disassembler` writes.
+///
+/// A file is a list of sections, the first one being the returned function
+/// and every later one a function constant, in the order the disassembler
+/// lists them:
+///
+/// ```text
+/// == BEGIN script ==
+/// .line 1
+/// start:
+///     OP_Constant 1.5          ; appends a new constant
+///     OP_Constant 0 1.5        ; or names its index
+///     OP_Add
+///     OP_Return
+/// == END   script ==
+/// ```
+///
+/// Instructions may start with the offset and line columns of a listing,
+/// where `|` repeats the previous line; `.line N` sets the line of the
+/// instructions that follow instead. Constants are `nil`, `true`, `false`,
+/// integers, floats (`2.0`), quoted strings and `<fn name>`, which refers to
+/// the next section. Global, property and method names must be strings;
+/// given without an index they reuse an existing string constant, like the
+/// compiler does. `.const N value` defines
+/// a constant no instruction refers to and `.arity N` sets the arity of a
+/// function. `name:` labels an offset; the instruction set has no jumps yet,
+/// so labels are only checked for duplicates. Every section has to end with
+/// `OP_Return`, so execution can not run past its code.
+pub fn assemble(source: &str, file: &str, heap: &mut Heap) -> Res<Function> {
+    let mut sections = Assembler::new(source).sections()?.into_iter();
+    let first = sections.next().ok_or_else(|| {
+        let span = Span::new(source.len(), source.len(), source.lines().count().max(1));
+        Compile::new(ErrorKind::SyntaxError, "Expect a section.").at(span, "end")
+    })?;
+
+    let file: Arc<str> = file.into();
+    let function = build(first, &mut sections, &file, heap)?;
+    if let Some(extra) = sections.next() {
+        return Err(extra.error(&format!(
+            "Function '{}' is not referenced by any constant.",
+            extra.name
+        )));
+    }
+    Ok(function)
+}
+
+/// A constant as written in the source, before allocation.
+#[derive(Debug, Clone)]
+enum Const {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Function(String),
+}
+
+impl Const {
+    fn same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Nil, Self::Nil) => true,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::String(a), Self::String(b)) | (Self::Function(a), Self::Function(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+struct Section {
+    name: String,
+    header: Span,
+    arity: u8,
+    code: Vec<(Opcode, usize)>,
+    constants: Vec<Option<Const>>,
+    labels: Vec<(String, usize)>,
+}
+
+impl Section {
+    fn new(name: &str, header: Span) -> Self {
+        Self {
+            name: name.to_owned(),
+            header,
+            arity: 0,
+            code: Vec::new(),
+            constants: Vec::new(),
+            labels: Vec::new(),
+        }
+    }
+
+    fn error(&self, msg: &str) -> RxError {
+        let lexeme = format!("'{}'", self.name);
+        RxError::new(Compile::new(ErrorKind::SyntaxError, msg).at(self.header, &lexeme))
+    }
+}
+
+/// A whitespace separated word of a line; strings, `<...>` and `(...)` may
+/// contain spaces.
+#[derive(Clone, Copy)]
+struct Word<'src> {
+    text: &'src str,
+    span: Span,
+}
+
+struct Assembler<'src> {
+    source: &'src str,
+    sections: Vec<Section>,
+    /// Whether the last section has seen its `== END` line.
+    closed: bool,
+    line: usize,
+}
+
+impl<'src> Assembler<'src> {
+    fn new(source: &'src str) -> Self {
+        Self {
+            source,
+            sections: Vec::new(),
+            closed: true,
+            line: 1,
+        }
+    }
+
+    fn sections(mut self) -> Res<Vec<Section>> {
+        let mut offset = 0;
+        for (idx, text) in self.source.split_inclusive('\n').enumerate() {
+            let words = split(text, offset, idx + 1);
+            offset += text.len();
+            if !words.is_empty() {
+                self.line_of(&words)?;
+            }
+        }
+
+        for section in &self.sections {
+            if let Some(idx) = section.constants.iter().position(Option::is_none) {
+                return Err(section.error(&format!("Constant {idx} is never defined.")));
+            }
+            if !matches!(section.code.last(), Some((Opcode::Return, _))) {
+                return Err(section.error("Expect the section to end with OP_Return."));
+            }
+        }
+        Ok(self.sections)
+    }
+
+    fn line_of(&mut self, words: &[Word<'src>]) -> Res<()> {
+        let first = words[0];
+        let span = first.span.combine(&words[words.len() - 1].span);
+
+        if first.text == "==" {
+            return self.header(words, span);
+        }
+        if first.text.starts_with('.') {
+            return self.directive(words);
+        }
+        if let [label] = words {
+            if let Some(name) = label.text.strip_suffix(':') {
+                let section = self.section(span);
+                let offset = section.code.len();
+                if section.labels.iter().any(|(label, _)| label == name) {
+                    return Err(error(*label, "Label is already defined."));
+                }
+                section.labels.push((name.to_owned(), offset));
+                return Ok(());
+            }
+        }
+        self.instruction(words)
+    }
+
+    /// `== BEGIN name ==` or `== END   name ==`.
+    fn header(&mut self, words: &[Word<'src>], span: Span) -> Res<()> {
+        let [_, kind, name, end] = words else {
+            return Err(error(
+                words[0],
+                "Expect '== BEGIN name ==' or '== END name =='.",
+            ));
+        };
+        if end.text != "==" {
+            return Err(error(*end, "Expect '==' after section name."));
+        }
+
+        match kind.text {
+            "BEGIN" => {
+                self.sections.push(Section::new(name.text, span));
+                self.closed = false;
+                self.line = 1;
+            }
+            "END" => {
+                let open = self.sections.last().filter(|_| !self.closed);
+                if open.is_none_or(|section| section.name != name.text) {
+                    return Err(error(*name, "Section name does not match its BEGIN."));
+                }
+                self.closed = true;
+            }
+            _ => return Err(error(*kind, "Expect 'BEGIN' or 'END'.")),
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, words: &[Word<'src>]) -> Res<()> {
+        let span = words[0].span;
+        match (words[0].text, &words[1..]) {
+            (".line", [line]) => self.line = number(*line)?,
+            (".arity", [arity]) => self.section(span).arity = number(*arity)?,
+            (".const", [idx_word, value]) => {
+                let idx = number(*idx_word)?;
+                let value = constant(*value)?;
+                self.define(idx, value, *idx_word)?;
+            }
+            (".line" | ".arity" | ".const", _) => {
+                return Err(error(words[0], "Wrong number of operands."));
+            }
+            _ => return Err(error(words[0], "Unknown directive.")),
+        }
+        Ok(())
+    }
+
+    /// `[offset] [line | '|'] OP_Name operands...`
+    fn instruction(&mut self, words: &[Word<'src>]) -> Res<()> {
+        let Some(at) = words.iter().position(|word| word.text.starts_with("OP_")) else {
+            return Err(error(words[0], "Expect an instruction."));
+        };
+        match &words[..at] {
+            [] => (),
+            [line] | [_, line] if line.text == "|" => (),
+            [line] | [_, line] => self.line = number(*line)?,
+            _ => {
+                return Err(error(
+                    words[0],
+                    "Expect offset and line before instruction.",
+                ))
+            }
+        }
+
+        let name = words[at];
+        let operands = &words[at + 1..];
+        let op = match (name.text, operands) {
+            ("OP_Nil", []) => Opcode::Nil,
+            ("OP_True", []) => Opcode::True,
+            ("OP_False", []) => Opcode::False,
+            ("OP_Pop", []) => Opcode::Pop,
+            ("OP_Equal", []) => Opcode::Equal,
+            ("OP_Greater", []) => Opcode::Greater,
+            ("OP_Less", []) => Opcode::Less,
+            ("OP_Add", []) => Opcode::Add,
+            ("OP_Subtract", []) => Opcode::Subtract,
+            ("OP_Multiply", []) => Opcode::Multiply,
+            ("OP_Divide", []) => Opcode::Divide,
+            ("OP_Negate", []) => Opcode::Negate,
+            ("OP_Not", []) => Opcode::Not,
+            ("OP_Return", []) => Opcode::Return,
+            ("OP_GetLocal", [slot]) => Opcode::GetLocal(number(*slot)?),
+            ("OP_SetLocal", [slot]) => Opcode::SetLocal(number(*slot)?),
+            ("OP_Concat", [parts]) => Opcode::Concat(number(*parts)?),
+            ("OP_Call", [argc]) => Opcode::Call(number(*argc)?),
+            ("OP_Constant", operands) => Opcode::Constant(self.operand(operands, name, false)?),
+            ("OP_DefineGlobal", operands) => {
+                Opcode::DefineGlobal(self.operand(operands, name, true)?)
+            }
+            ("OP_GetGlobal", operands) => Opcode::GetGlobal(self.operand(operands, name, true)?),
+            ("OP_SetGlobal", operands) => Opcode::SetGlobal(self.operand(operands, name, true)?),
+            ("OP_GetProperty", operands) => {
+                Opcode::GetProperty(self.operand(operands, name, true)?)
+            }
+            ("OP_SetProperty", operands) => {
+                Opcode::SetProperty(self.operand(operands, name, true)?)
+            }
+            ("OP_Invoke", [argc, operands @ ..]) => {
+                let argc = match argc.text.strip_prefix('(') {
+                    Some(text) => {
+                        let text = text.strip_suffix("args)").unwrap_or(text).trim();
+                        number(Word { text, ..*argc })?
+                    }
+                    None => number(*argc)?,
+                };
+                Opcode::Invoke(self.operand(operands, name, true)?, argc)
+            }
+            (
+                "OP_Nil" | "OP_True" | "OP_False" | "OP_Pop" | "OP_Equal" | "OP_Greater"
+                | "OP_Less" | "OP_Add" | "OP_Subtract" | "OP_Multiply" | "OP_Divide" | "OP_Negate"
+                | "OP_Not" | "OP_Return" | "OP_GetLocal" | "OP_SetLocal" | "OP_Concat" | "OP_Call"
+                | "OP_Invoke",
+                _,
+            ) => return Err(error(name, "Wrong number of operands.")),
+            _ => return Err(error(name, "Unknown instruction.")),
+        };
+
+        let line = self.line;
+        self.section(name.span).code.push((op, line));
+        Ok(())
+    }
+
+    /// `[index] value` operand of an instruction that refers to a constant.
+    fn operand(&mut self, operands: &[Word<'src>], op: Word<'src>, is_name: bool) -> Res<u8> {
+        match operands {
+            [idx_word, value_word] => {
+                let idx = number(*idx_word)?;
+                let value = name_or_constant(*value_word, is_name)?;
+                self.define(idx, value, *idx_word)?;
+                Ok(idx)
+            }
+            [word] => {
+                let value = name_or_constant(*word, is_name)?;
+                let section = self.section(word.span);
+                let existing = match &value {
+                    Const::String(_) if is_name => section
+                        .constants
+                        .iter()
+                        .position(|c| c.as_ref().is_some_and(|c| c.same(&value))),
+                    _ => None,
+                };
+
+                match existing {
+                    Some(idx) => Ok(idx as u8),
+                    None => {
+                        let idx = u8::try_from(section.constants.len())
+                            .map_err(|_| error(op, "Too many constants in one chunk."))?;
+                        section.constants.push(Some(value));
+                        Ok(idx)
+                    }
+                }
+            }
+            _ => Err(error(op, "Wrong number of operands.")),
+        }
+    }
+
+    fn define(&mut self, idx: u8, value: Const, at: Word<'src>) -> Res<()> {
+        let section = self.section(at.span);
+        let idx = idx as usize;
+        if section.constants.len() <= idx {
+            section.constants.resize(idx + 1, None);
+        }
+
+        match &section.constants[idx] {
+            Some(existing) if !existing.same(&value) => {
+                Err(error(at, "Constant is already defined as another value."))
+            }
+            _ => {
+                section.constants[idx] = Some(value);
+                Ok(())
+            }
+        }
+    }
+
+    /// Section the next line belongs to, starting an implicit `script`
+    /// section for text without headers.
+    fn section(&mut self, span: Span) -> &mut Section {
+        if self.closed {
+            self.sections.push(Section::new("script", span));
+            self.closed = false;
+        }
+        self.sections.last_mut().expect("No open section")
+    }
+}
+
+/// Turns a parsed section into a function, building the sections of its
+/// function constants from `rest` first.
+fn build(
+    section: Section,
+    rest: &mut impl Iterator<Item = Section>,
+    file: &Arc<str>,
+    heap: &mut Heap,
+) -> Res<Function> {
+    let mut function = Function::new(section.name.as_str().into(), file.clone());
+    function.arity = section.arity;
+
+    let mut chunk = Chunk::new();
+    for constant in section.constants.into_iter().flatten() {
+        let value = match constant {
+            Const::Nil => Value::Nil,
+            Const::Bool(b) => Value::Bool(b),
+            Const::Int(int) => Value::Int(int),
+            Const::Float(float) => Value::Float(float),
+            Const::String(string) => Value::String(heap.alloc_string(string)?),
+            Const::Function(name) => {
+                let Some(next) = rest.next() else {
+                    return Err(section_error(&section.name, section.header, &name));
+                };
+                if next.name != name {
+                    return Err(next.error(&format!("Expect the section of function '{name}'.")));
+                }
+                let function = build(next, rest, file, heap)?;
+                Value::Function(heap.alloc_function(function)?)
+            }
+        };
+        chunk.constants.push(value);
+    }
+    for (op, line) in section.code {
+        chunk.write(op, line);
+    }
+
+    function.chunk = chunk;
+    Ok(function)
+}
+
+fn section_error(section: &str, header: Span, name: &str) -> RxError {
+    let msg = format!("Missing the section of function '{name}'.");
+    let lexeme = format!("'{section}'");
+    RxError::new(Compile::new(ErrorKind::SyntaxError, &msg).at(header, &lexeme))
+}
+
+fn error(word: Word, msg: &str) -> RxError {
+    let lexeme = format!("'{}'", word.text);
+    RxError::new(Compile::new(ErrorKind::SyntaxError, msg).at(word.span, &lexeme))
+}
+
+/// Constant operand, which must be a string when it names a global,
+/// property or method.
+fn name_or_constant(word: Word, is_name: bool) -> Res<Const> {
+    let value = constant(word)?;
+    if is_name && !matches!(value, Const::String(_)) {
+        return Err(error(word, "Expect a string constant as the name."));
+    }
+    Ok(value)
+}
+
+fn number<T: std::str::FromStr>(word: Word) -> Res<T> {
+    word.text
+        .parse()
+        .map_err(|_| error(word, "Expect a number in range."))
+}
+
+fn constant(word: Word) -> Res<Const> {
+    let text = word.text;
+    let value = match text {
+        "nil" => Const::Nil,
+        "true" => Const::Bool(true),
+        "false" => Const::Bool(false),
+        _ if text.starts_with('"') => {
+            let body = text
+                .strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+                .filter(|_| text.len() >= 2)
+                .ok_or_else(|| error(word, "Unterminated string."))?;
+            let string = unescape(body).map_err(|e| error(word, e.msg))?;
+            Const::String(string)
+        }
+        _ if text.starts_with("<fn ") && text.ends_with('>') => {
+            Const::Function(text[4..text.len() - 1].to_owned())
+        }
+        _ => match (text.parse(), text.parse()) {
+            (Ok(int), _) => Const::Int(int),
+            (_, Ok(float)) => Const::Float(float),
+            _ => return Err(error(word, "Expect a constant.")),
+        },
+    };
+    Ok(value)
+}
+
+/// Splits `text`, which starts at byte `offset` of the source, into words,
+/// dropping `;` comments.
+fn split(text: &str, offset: usize, line: usize) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == ';' {
+            break;
+        }
+
+        let close = match c {
+            '"' => Some('"'),
+            '<' => Some('>'),
+            '(' => Some(')'),
+            _ => None,
+        };
+        chars.next();
+
+        let mut end = text.trim_end_matches(['\n', '\r']).len();
+        let mut escaped = false;
+        while let Some(&(idx, c)) = chars.peek() {
+            match close {
+                Some(close) if c == close && !escaped => {
+                    chars.next();
+                    end = idx + c.len_utf8();
+                    break;
+                }
+                Some(_) => escaped = c == '\\' && !escaped && close == Some('"'),
+                None if c.is_whitespace() || c == ';' => {
+                    end = idx;
+                    break;
+                }
+                None => (),
+            }
+            chars.next();
+        }
+
+        let span = Span::new(offset + start, offset + end, line);
+        words.push(Word {
+            text: &text[start..end],
+            span,
+        });
+    }
+    words
+}
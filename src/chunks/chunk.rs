@@ -1,12 +1,16 @@
-use crate::value::Value;
+use crate::{
+    error::{Compile, ErrorKind, RxError},
+    value::Value,
+    Res,
+};
 
-use super::Opcode;
+use super::{LineTable, Opcode};
 
 #[derive(Debug, Default)]
 pub struct Chunk {
     pub code: Vec<Opcode>,
     pub constants: Vec<Value>,
-    pub lines: Vec<usize>,
+    pub lines: LineTable,
 }
 
 impl Chunk {
@@ -19,15 +23,27 @@ impl Chunk {
         self.lines.push(line);
     }
 
-    pub fn add_constant<T>(&mut self, value: T) -> u8
+    /// Appends a constant and returns its index, failing once the chunk
+    /// already holds as many constants as a `u8` operand can address.
+    pub fn add_constant<T>(&mut self, value: T) -> Res<u8>
     where
         T: Into<Value>,
     {
+        let idx = u8::try_from(self.constants.len()).map_err(|_| {
+            RxError::new(Compile::new(
+                ErrorKind::SyntaxError,
+                "Too many constants in one chunk.",
+            ))
+        })?;
         self.constants.push(value.into());
-        self.constants.len() as u8 - 1
+        Ok(idx)
+    }
+
+    pub fn line_for(&self, offset: usize) -> usize {
+        self.lines.line_for(offset).unwrap_or_default()
     }
 
     pub fn read_constant(&self, constant: u8) -> Value {
-        self.constants[constant as usize]
+        self.constants[constant as usize].clone()
     }
 }
@@ -1,79 +1,165 @@
-#[cfg(feature = "trace")]
+use std::io::{self, Write};
+
 use crate::{
     chunks::{Chunk, Opcode},
     value::Value,
 };
 
-#[cfg(feature = "trace")]
 pub struct Disassembler<'src> {
     chunk: &'src Chunk,
-    stack: Option<&'src Vec<Value>>,
+    stack: Option<&'src [Value]>,
 }
 
-#[cfg(feature = "trace")]
 impl<'src> Disassembler<'src> {
-    pub fn new(chunk: &'src Chunk, stack: Option<&'src Vec<Value>>) -> Self {
+    pub fn new(chunk: &'src Chunk, stack: Option<&'src [Value]>) -> Self {
         Self { chunk, stack }
     }
 
-    pub fn disassemble(&self, name: &str) {
-        println!("== BEGIN {} ==", name);
+    /// Writes the listing of the chunk, then of every function among its
+    /// constants. The output can be read back by `assemble`.
+    pub fn disassemble(&self, out: &mut dyn Write, name: &str) -> io::Result<()> {
+        self.section(out, name, None)
+    }
+
+    fn section(&self, out: &mut dyn Write, name: &str, arity: Option<u8>) -> io::Result<()> {
+        writeln!(out, "== BEGIN {} ==", name)?;
+        if let Some(arity) = arity {
+            writeln!(out, ".arity {arity}")?;
+        }
 
         for (idx, op) in self.chunk.code.iter().enumerate() {
-            self.instruction(idx, op)
+            self.instruction(out, idx, op)?;
         }
 
-        println!("== END   {} ==\n", name);
-    }
+        // constants no instruction refers to would be lost otherwise
+        let mut used = vec![false; self.chunk.constants.len()];
+        for op in &self.chunk.code {
+            if let Some(idx) = op.constant() {
+                used[idx as usize] = true;
+            }
+        }
+        for (idx, constant) in self.chunk.constants.iter().enumerate() {
+            if !used[idx] {
+                write!(out, ".const {idx} ")?;
+                write_constant(out, constant)?;
+                writeln!(out)?;
+            }
+        }
 
-    fn stack(&self) {
-        if let Some(stack) = self.stack {
-            print!("S: ");
-            if !stack.is_empty() {
-                for value in stack {
-                    print!("[{}]", value);
-                }
-                println!();
-            } else {
-                println!("[]");
+        writeln!(out, "== END   {} ==\n", name)?;
+
+        for constant in &self.chunk.constants {
+            if let Value::Function(function) = constant {
+                let function = function.get();
+                Disassembler::new(&function.chunk, None).section(
+                    out,
+                    &function.name,
+                    Some(function.arity),
+                )?;
             }
         }
+        Ok(())
     }
 
-    pub fn instruction(&self, offset: usize, opcode: &Opcode) {
-        self.stack();
-        print!("{:04} ", offset);
-        let line = self.chunk.lines[offset];
-        if offset > 0 && line == self.chunk.lines[offset - 1] {
-            print!("   | ")
-        } else {
-            print!("{:>4} ", line)
+    fn stack(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self.stack {
+            Some(stack) => write_stack(out, stack),
+            None => Ok(()),
         }
+    }
 
-        match opcode {
-            Opcode::Constant(c) => self.const_op("OP_Constant", *c),
-            Opcode::Nil => self.simple_op("OP_Nil"),
-            Opcode::True => self.simple_op("OP_True"),
-            Opcode::False => self.simple_op("OP_False"),
-            Opcode::Equal => self.simple_op("OP_Equal"),
-            Opcode::Greater => self.simple_op("OP_Greater"),
-            Opcode::Less => self.simple_op("OP_Less"),
-            Opcode::Add => self.simple_op("OP_Add"),
-            Opcode::Subtract => self.simple_op("OP_Subtract"),
-            Opcode::Multiply => self.simple_op("OP_Multiply"),
-            Opcode::Divide => self.simple_op("OP_Divide"),
-            Opcode::Negate => self.simple_op("OP_Negate"),
-            Opcode::Not => self.simple_op("OP_Not"),
-            Opcode::Return => self.simple_op("OP_Return"),
+    pub fn instruction(
+        &self,
+        out: &mut dyn Write,
+        offset: usize,
+        opcode: &Opcode,
+    ) -> io::Result<()> {
+        self.stack(out)?;
+        write!(out, "{:04} ", offset)?;
+        let line = self.chunk.line_for(offset);
+        if offset > 0 && line == self.chunk.line_for(offset - 1) {
+            write!(out, "   | ")?;
+        } else {
+            write!(out, "{:>4} ", line)?;
         }
+
+        write_op(out, self.chunk, opcode)
     }
+}
 
-    fn simple_op(&self, name: &str) {
-        println!("{name}");
+/// Writes a stack snapshot as `S: [a][b]`.
+pub fn write_stack(out: &mut dyn Write, stack: &[Value]) -> io::Result<()> {
+    write!(out, "S: ")?;
+    if stack.is_empty() {
+        return writeln!(out, "[]");
     }
 
-    fn const_op(&self, name: &str, idx: u8) {
-        let value = self.chunk.constants[idx as usize];
-        println!("{:<16} {:4}", name, value)
+    for value in stack {
+        write!(out, "[{}]", value)?;
+    }
+    writeln!(out)
+}
+
+/// Writes `opcode` and its operands, resolving constants against `chunk`.
+pub fn write_op(out: &mut dyn Write, chunk: &Chunk, opcode: &Opcode) -> io::Result<()> {
+    let name = opcode.name();
+    match *opcode {
+        Opcode::Constant(c)
+        | Opcode::DefineGlobal(c)
+        | Opcode::GetGlobal(c)
+        | Opcode::SetGlobal(c)
+        | Opcode::GetProperty(c)
+        | Opcode::SetProperty(c) => const_op(out, chunk, name, c),
+        Opcode::GetLocal(byte)
+        | Opcode::SetLocal(byte)
+        | Opcode::Concat(byte)
+        | Opcode::Call(byte) => byte_op(out, name, byte),
+        Opcode::Invoke(c, argc) => invoke_op(out, chunk, name, c, argc),
+        _ => simple_op(out, name),
+    }
+}
+
+fn simple_op(out: &mut dyn Write, name: &str) -> io::Result<()> {
+    writeln!(out, "{name}")
+}
+
+fn byte_op(out: &mut dyn Write, name: &str, byte: u8) -> io::Result<()> {
+    writeln!(out, "{:<16} {:4}", name, byte)
+}
+
+fn invoke_op(out: &mut dyn Write, chunk: &Chunk, name: &str, idx: u8, argc: u8) -> io::Result<()> {
+    write!(out, "{:<16} ({} args) {:4} ", name, argc, idx)?;
+    write_constant(out, &chunk.constants[idx as usize])?;
+    writeln!(out)
+}
+
+fn const_op(out: &mut dyn Write, chunk: &Chunk, name: &str, idx: u8) -> io::Result<()> {
+    write!(out, "{:<16} {:4} ", name, idx)?;
+    write_constant(out, &chunk.constants[idx as usize])?;
+    writeln!(out)
+}
+
+/// Writes a constant so that `assemble` reads back the same value: strings
+/// are quoted and escaped, floats always keep a fraction or exponent.
+pub fn write_constant(out: &mut dyn Write, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Float(float) => write!(out, "{float:?}"),
+        Value::String(string) => {
+            write!(out, "\"")?;
+            for c in string.get().chars() {
+                match c {
+                    '"' => write!(out, "\\\"")?,
+                    '\\' => write!(out, "\\\\")?,
+                    '\n' => write!(out, "\\n")?,
+                    '\r' => write!(out, "\\r")?,
+                    '\t' => write!(out, "\\t")?,
+                    '\0' => write!(out, "\\0")?,
+                    c if c.is_control() => write!(out, "\\u{{{:x}}}", c as u32)?,
+                    c => write!(out, "{c}")?,
+                }
+            }
+            write!(out, "\"")
+        }
+        value => write!(out, "{value}"),
     }
 }
@@ -1,9 +1,17 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Opcode {
     Constant(u8),
     Nil,
     True,
     False,
+    Pop,
+    GetLocal(u8),
+    SetLocal(u8),
+    DefineGlobal(u8),
+    GetGlobal(u8),
+    SetGlobal(u8),
+    GetProperty(u8),
+    SetProperty(u8),
     Equal,
     Greater,
     Less,
@@ -13,5 +21,103 @@ pub enum Opcode {
     Divide,
     Negate,
     Not,
+    /// Joins the top N values into one string.
+    Concat(u8),
+    Call(u8),
+    Invoke(u8, u8),
     Return,
 }
+
+impl Opcode {
+    /// Mnemonics of every opcode, in declaration order.
+    pub const NAMES: [&'static str; 25] = [
+        "OP_Constant",
+        "OP_Nil",
+        "OP_True",
+        "OP_False",
+        "OP_Pop",
+        "OP_GetLocal",
+        "OP_SetLocal",
+        "OP_DefineGlobal",
+        "OP_GetGlobal",
+        "OP_SetGlobal",
+        "OP_GetProperty",
+        "OP_SetProperty",
+        "OP_Equal",
+        "OP_Greater",
+        "OP_Less",
+        "OP_Add",
+        "OP_Subtract",
+        "OP_Multiply",
+        "OP_Divide",
+        "OP_Negate",
+        "OP_Not",
+        "OP_Concat",
+        "OP_Call",
+        "OP_Invoke",
+        "OP_Return",
+    ];
+
+    /// Mnemonic used by the disassembler, e.g. `OP_Constant`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Constant(_) => "OP_Constant",
+            Self::Nil => "OP_Nil",
+            Self::True => "OP_True",
+            Self::False => "OP_False",
+            Self::Pop => "OP_Pop",
+            Self::GetLocal(_) => "OP_GetLocal",
+            Self::SetLocal(_) => "OP_SetLocal",
+            Self::DefineGlobal(_) => "OP_DefineGlobal",
+            Self::GetGlobal(_) => "OP_GetGlobal",
+            Self::SetGlobal(_) => "OP_SetGlobal",
+            Self::GetProperty(_) => "OP_GetProperty",
+            Self::SetProperty(_) => "OP_SetProperty",
+            Self::Equal => "OP_Equal",
+            Self::Greater => "OP_Greater",
+            Self::Less => "OP_Less",
+            Self::Add => "OP_Add",
+            Self::Subtract => "OP_Subtract",
+            Self::Multiply => "OP_Multiply",
+            Self::Divide => "OP_Divide",
+            Self::Negate => "OP_Negate",
+            Self::Not => "OP_Not",
+            Self::Concat(_) => "OP_Concat",
+            Self::Call(_) => "OP_Call",
+            Self::Invoke(_, _) => "OP_Invoke",
+            Self::Return => "OP_Return",
+        }
+    }
+
+    /// Index of the constant the instruction refers to, if any.
+    pub fn constant(&self) -> Option<u8> {
+        match *self {
+            Self::Constant(idx)
+            | Self::DefineGlobal(idx)
+            | Self::GetGlobal(idx)
+            | Self::SetGlobal(idx)
+            | Self::GetProperty(idx)
+            | Self::SetProperty(idx)
+            | Self::Invoke(idx, _) => Some(idx),
+            _ => None,
+        }
+    }
+
+    /// Raw operand bytes, in encoding order.
+    pub fn operands(&self) -> Vec<u8> {
+        match *self {
+            Self::Constant(byte)
+            | Self::GetLocal(byte)
+            | Self::SetLocal(byte)
+            | Self::DefineGlobal(byte)
+            | Self::GetGlobal(byte)
+            | Self::SetGlobal(byte)
+            | Self::GetProperty(byte)
+            | Self::SetProperty(byte)
+            | Self::Concat(byte)
+            | Self::Call(byte) => vec![byte],
+            Self::Invoke(name, argc) => vec![name, argc],
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/// Run-length encoded mapping from instruction offsets to source lines.
+///
+/// Consecutive instructions emitted for the same line share a single run, so a
+/// script costs one entry per line change rather than one per instruction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LineTable {
+    runs: Vec<LineRun>,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LineRun {
+    start: usize,
+    line: usize,
+}
+
+impl LineTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the line of the next instruction.
+    pub fn push(&mut self, line: usize) {
+        match self.runs.last() {
+            Some(run) if run.line == line => (),
+            _ => self.runs.push(LineRun {
+                start: self.len,
+                line,
+            }),
+        }
+        self.len += 1;
+    }
+
+    /// Returns the line of the instruction at `offset`, if there is one.
+    pub fn line_for(&self, offset: usize) -> Option<usize> {
+        if offset >= self.len {
+            return None;
+        }
+
+        let idx = self.runs.partition_point(|run| run.start <= offset);
+        Some(self.runs[idx - 1].line)
+    }
+
+    /// Number of instructions covered by the table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineTable;
+
+    fn table(lines: &[usize]) -> LineTable {
+        let mut table = LineTable::new();
+        for &line in lines {
+            table.push(line);
+        }
+        table
+    }
+
+    #[test]
+    fn first_and_last_offset_of_a_run() {
+        let table = table(&[1, 1, 1]);
+        assert_eq!(table.runs.len(), 1);
+        assert_eq!(table.line_for(0), Some(1));
+        assert_eq!(table.line_for(2), Some(1));
+    }
+
+    #[test]
+    fn consecutive_runs() {
+        let table = table(&[1, 1, 2, 2, 3]);
+        assert_eq!(table.runs.len(), 3);
+        assert_eq!(table.line_for(1), Some(1));
+        assert_eq!(table.line_for(2), Some(2));
+        assert_eq!(table.line_for(3), Some(2));
+        assert_eq!(table.line_for(4), Some(3));
+    }
+
+    #[test]
+    fn line_gaps_and_returning_lines() {
+        let table = table(&[1, 7, 7, 40, 7]);
+        assert_eq!(table.runs.len(), 4);
+        assert_eq!(table.line_for(0), Some(1));
+        assert_eq!(table.line_for(1), Some(7));
+        assert_eq!(table.line_for(3), Some(40));
+        assert_eq!(table.line_for(4), Some(7));
+    }
+
+    #[test]
+    fn offsets_past_the_end() {
+        let table = table(&[1, 2]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.line_for(2), None);
+        assert_eq!(table.line_for(usize::MAX), None);
+        assert_eq!(LineTable::new().line_for(0), None);
+    }
+}
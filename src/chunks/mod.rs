@@ -1,7 +1,7 @@
+mod assembler;
 mod chunk;
 mod disassmbler;
 mod instruction;
+mod lines;
 
-#[cfg(feature = "trace")]
-pub use self::disassmbler::*;
-pub use self::{chunk::*, instruction::*};
+pub use self::{assembler::*, chunk::*, disassmbler::*, instruction::*, lines::*};
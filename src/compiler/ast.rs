@@ -0,0 +1,291 @@
+//! Syntax tree produced by the `Parser`.
+//!
+//! Every node carries the `Span` of the source text it was parsed from; the
+//! line of a span is the line its first token is on. `Script` displays as an
+//! indented tree, one node per line prefixed with its line number.
+
+use std::fmt;
+
+use super::Span;
+
+/// A parsed source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    pub body: Vec<Stmt>,
+    /// Trailing expression without a `;`, which the script evaluates to.
+    pub result: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StmtKind {
+    /// `var name = init;`
+    Var { name: Ident, init: Option<Expr> },
+    /// `fn name(params) { body }`
+    Fn(FnDecl),
+    /// `{ ... }`
+    Block(Vec<Stmt>),
+    /// `return value;`
+    Return(Option<Expr>),
+    /// `expr;`
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnDecl {
+    pub name: Ident,
+    pub params: Vec<Ident>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Literal(Literal),
+    /// `"a ${b} c"`, string segments appearing as string literals.
+    Interpolation(Vec<Expr>),
+    Variable(String),
+    Assign {
+        name: String,
+        value: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    Grouping(Box<Expr>),
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+    },
+    /// `object.name`
+    Get {
+        object: Box<Expr>,
+        name: Ident,
+    },
+    /// `object.name = value`
+    Set {
+        object: Box<Expr>,
+        name: Ident,
+        value: Box<Expr>,
+    },
+    /// `object.name(args)`
+    Invoke {
+        object: Box<Expr>,
+        name: Ident,
+        args: Vec<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    /// Contents with escapes resolved.
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stmt in &self.body {
+            write_stmt(f, stmt, 0)?;
+        }
+        if let Some(result) = &self.result {
+            node(f, result.span, 0, "Result")?;
+            write_expr(f, result, 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Negate => "-",
+            Self::Not => "!",
+        })
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+        })
+    }
+}
+
+fn node(f: &mut fmt::Formatter<'_>, span: Span, depth: usize, label: &str) -> fmt::Result {
+    writeln!(f, "{:>4} {:indent$}{label}", span.2, "", indent = depth * 2)
+}
+
+fn write_stmt(f: &mut fmt::Formatter<'_>, stmt: &Stmt, depth: usize) -> fmt::Result {
+    let span = stmt.span;
+    match &stmt.kind {
+        StmtKind::Var { name, init } => {
+            node(f, span, depth, &format!("Var {}", name.name))?;
+            if let Some(init) = init {
+                write_expr(f, init, depth + 1)?;
+            }
+        }
+        StmtKind::Fn(decl) => {
+            let params = decl
+                .params
+                .iter()
+                .map(|param| param.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            node(f, span, depth, &format!("Fn {}({params})", decl.name.name))?;
+            for stmt in &decl.body {
+                write_stmt(f, stmt, depth + 1)?;
+            }
+        }
+        StmtKind::Block(body) => {
+            node(f, span, depth, "Block")?;
+            for stmt in body {
+                write_stmt(f, stmt, depth + 1)?;
+            }
+        }
+        StmtKind::Return(value) => {
+            node(f, span, depth, "Return")?;
+            if let Some(value) = value {
+                write_expr(f, value, depth + 1)?;
+            }
+        }
+        StmtKind::Expr(expr) => {
+            node(f, span, depth, "Expr")?;
+            write_expr(f, expr, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_expr(f: &mut fmt::Formatter<'_>, expr: &Expr, depth: usize) -> fmt::Result {
+    let span = expr.span;
+    let children: Vec<&Expr> = match &expr.kind {
+        ExprKind::Literal(literal) => {
+            let label = match literal {
+                Literal::Nil => "Nil".to_owned(),
+                Literal::Bool(b) => format!("Bool {b}"),
+                Literal::Int(int) => format!("Int {int}"),
+                Literal::Float(float) => format!("Float {float:?}"),
+                Literal::String(string) => format!("String {string:?}"),
+            };
+            node(f, span, depth, &label)?;
+            Vec::new()
+        }
+        ExprKind::Interpolation(parts) => {
+            node(f, span, depth, "Interpolation")?;
+            parts.iter().collect()
+        }
+        ExprKind::Variable(name) => {
+            node(f, span, depth, &format!("Variable {name}"))?;
+            Vec::new()
+        }
+        ExprKind::Assign { name, value } => {
+            node(f, span, depth, &format!("Assign {name}"))?;
+            vec![value]
+        }
+        ExprKind::Unary { op, expr } => {
+            node(f, span, depth, &format!("Unary {op}"))?;
+            vec![expr]
+        }
+        ExprKind::Binary { op, lhs, rhs } => {
+            node(f, span, depth, &format!("Binary {op}"))?;
+            vec![lhs, rhs]
+        }
+        ExprKind::Grouping(expr) => {
+            node(f, span, depth, "Grouping")?;
+            vec![expr]
+        }
+        ExprKind::Call { callee, args } => {
+            node(f, span, depth, "Call")?;
+            std::iter::once(&**callee).chain(args).collect()
+        }
+        ExprKind::Get { object, name } => {
+            node(f, span, depth, &format!("Get .{}", name.name))?;
+            vec![object]
+        }
+        ExprKind::Set {
+            object,
+            name,
+            value,
+        } => {
+            node(f, span, depth, &format!("Set .{}", name.name))?;
+            vec![object, value]
+        }
+        ExprKind::Invoke { object, name, args } => {
+            node(f, span, depth, &format!("Invoke .{}", name.name))?;
+            std::iter::once(&**object).chain(args).collect()
+        }
+    };
+
+    for child in children {
+        write_expr(f, child, depth + 1)?;
+    }
+    Ok(())
+}
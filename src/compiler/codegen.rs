@@ -0,0 +1,402 @@
+use std::sync::Arc;
+
+use crate::{
+    chunks::{Chunk, Opcode},
+    error::{Compile, ErrorKind, RxError},
+    object::{Function, Heap, ObjRef},
+    value::Value,
+    Res,
+};
+
+use super::{
+    ast::{BinaryOp, Expr, ExprKind, FnDecl, Ident, Literal, Script, Stmt, StmtKind, UnaryOp},
+    FunctionKind, FunctionScope, Local, Parser, Span,
+};
+
+/// Parses `source` and compiles it into the function for the top-level
+/// script.
+pub fn compile(source: &str, file: &str, heap: &mut Heap) -> Res<ObjRef<Function>> {
+    let script = Parser::new(source).parse()?;
+    Codegen::new(source, file, heap).generate(&script)
+}
+
+/// Walks a parsed `Script` and writes its bytecode into `Chunk`s.
+pub struct Codegen<'a> {
+    source: &'a str,
+    heap: &'a mut Heap,
+    file: Arc<str>,
+    scopes: Vec<FunctionScope<'a>>,
+
+    error: Option<Compile>,
+}
+
+impl<'a> Codegen<'a> {
+    pub fn new(source: &'a str, file: &str, heap: &'a mut Heap) -> Self {
+        let file: Arc<str> = file.into();
+        Self {
+            source,
+            heap,
+            scopes: vec![FunctionScope::new(
+                FunctionKind::Script,
+                "script".into(),
+                file.clone(),
+            )],
+            file,
+
+            error: None,
+        }
+    }
+
+    // pub api
+    pub fn generate(mut self, script: &'a Script) -> Res<ObjRef<Function>> {
+        for stmt in &script.body {
+            self.statement(stmt);
+        }
+
+        let line = self.end_line(Span::new(0, self.source.len(), 1));
+        match &script.result {
+            Some(result) => self.expression(result),
+            None => self.emit(Opcode::Nil, line),
+        }
+        self.emit(Opcode::Return, line);
+
+        if let Some(error) = self.error {
+            return Err(RxError::new(error));
+        }
+
+        let scope = self.scopes.pop().expect("No script scope");
+        self.heap.alloc_function(scope.function)
+    }
+
+    // statements
+    fn statement(&mut self, stmt: &'a Stmt) {
+        let line = stmt.span.2;
+
+        match &stmt.kind {
+            StmtKind::Var { name, init } => {
+                let global = self.declare_variable(name);
+                match init {
+                    Some(init) => self.expression(init),
+                    None => self.emit(Opcode::Nil, line),
+                }
+                self.define_variable(global, line);
+            }
+            StmtKind::Fn(decl) => {
+                let global = self.declare_variable(&decl.name);
+                self.mark_initialized();
+                self.function(decl, stmt.span);
+                self.define_variable(global, line);
+            }
+            StmtKind::Block(body) => {
+                self.begin_scope();
+                for stmt in body {
+                    self.statement(stmt);
+                }
+                self.end_scope(self.end_line(stmt.span));
+            }
+            StmtKind::Return(value) => {
+                if self.scope().kind == FunctionKind::Script {
+                    let keyword = Span::new(stmt.span.0, stmt.span.0 + "return".len(), line);
+                    self.error_at(keyword, "Can't return from top-level code.");
+                }
+
+                match value {
+                    Some(value) => self.expression(value),
+                    None => self.emit(Opcode::Nil, line),
+                }
+                self.emit(Opcode::Return, line);
+            }
+            StmtKind::Expr(expr) => {
+                self.expression(expr);
+                self.emit(Opcode::Pop, line);
+            }
+        }
+    }
+
+    fn function(&mut self, decl: &'a FnDecl, span: Span) {
+        self.scopes.push(FunctionScope::new(
+            FunctionKind::Function,
+            decl.name.name.as_str().into(),
+            self.file.clone(),
+        ));
+        self.begin_scope();
+
+        // the parser already rejects more than 255 parameters
+        self.scope().function.arity = decl.params.len() as u8;
+        for param in &decl.params {
+            self.declare_variable(param);
+            self.mark_initialized();
+        }
+        for stmt in &decl.body {
+            self.statement(stmt);
+        }
+
+        let line = self.end_line(span);
+        self.emit(Opcode::Nil, line);
+        self.emit(Opcode::Return, line);
+
+        let scope = self.scopes.pop().expect("No function scope");
+        match self.heap.alloc_function(scope.function) {
+            Ok(function) => self.emit_constant(Value::Function(function), span),
+            Err(e) => self.error_kind_at(span, e.kind(), &e.to_string()),
+        }
+    }
+
+    // expressions
+    fn expression(&mut self, expr: &'a Expr) {
+        let line = expr.span.2;
+
+        match &expr.kind {
+            ExprKind::Literal(literal) => match literal {
+                Literal::Nil => self.emit(Opcode::Nil, line),
+                Literal::Bool(true) => self.emit(Opcode::True, line),
+                Literal::Bool(false) => self.emit(Opcode::False, line),
+                Literal::Int(int) => self.emit_constant(Value::Int(*int), expr.span),
+                Literal::Float(float) => self.emit_constant(Value::Float(*float), expr.span),
+                Literal::String(string) => match self.heap.alloc_string(string.clone()) {
+                    Ok(s) => self.emit_constant(Value::String(s), expr.span),
+                    Err(e) => self.error_kind_at(expr.span, e.kind(), &e.to_string()),
+                },
+            },
+            ExprKind::Interpolation(parts) => {
+                for part in parts {
+                    self.expression(part);
+                }
+                match u8::try_from(parts.len()) {
+                    Ok(parts) => self.emit(Opcode::Concat(parts), line),
+                    Err(_) => self.error_at(expr.span, "Too many parts in an interpolated string."),
+                }
+            }
+            ExprKind::Variable(name) => {
+                let (get, _) = self.named_variable(name, expr.span);
+                self.emit(get, line);
+            }
+            ExprKind::Assign { name, value } => {
+                let (_, set) = self.named_variable(name, expr.span);
+                self.expression(value);
+                self.emit(set, line);
+            }
+            ExprKind::Unary { op, expr } => {
+                self.expression(expr);
+                match op {
+                    UnaryOp::Negate => self.emit(Opcode::Negate, line),
+                    UnaryOp::Not => self.emit(Opcode::Not, line),
+                }
+            }
+            ExprKind::Binary { op, lhs, rhs } => {
+                self.expression(lhs);
+                self.expression(rhs);
+                self.binary_op(*op, line);
+            }
+            ExprKind::Grouping(expr) => self.expression(expr),
+            ExprKind::Call { callee, args } => {
+                self.expression(callee);
+                for arg in args {
+                    self.expression(arg);
+                }
+                self.emit(Opcode::Call(args.len() as u8), line);
+            }
+            ExprKind::Get { object, name } => {
+                self.expression(object);
+                let name = self.identifier_constant(name);
+                self.emit(Opcode::GetProperty(name), line);
+            }
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => {
+                self.expression(object);
+                let name = self.identifier_constant(name);
+                self.expression(value);
+                self.emit(Opcode::SetProperty(name), line);
+            }
+            ExprKind::Invoke { object, name, args } => {
+                self.expression(object);
+                let name = self.identifier_constant(name);
+                for arg in args {
+                    self.expression(arg);
+                }
+                self.emit(Opcode::Invoke(name, args.len() as u8), line);
+            }
+        }
+    }
+
+    fn binary_op(&mut self, op: BinaryOp, line: usize) {
+        match op {
+            BinaryOp::NotEqual => self.emit_pair(Opcode::Equal, Opcode::Not, line),
+            BinaryOp::Equal => self.emit(Opcode::Equal, line),
+            BinaryOp::Greater => self.emit(Opcode::Greater, line),
+            BinaryOp::GreaterEqual => self.emit_pair(Opcode::Less, Opcode::Not, line),
+            BinaryOp::Less => self.emit(Opcode::Less, line),
+            BinaryOp::LessEqual => self.emit_pair(Opcode::Greater, Opcode::Not, line),
+            BinaryOp::Add => self.emit(Opcode::Add, line),
+            BinaryOp::Subtract => self.emit(Opcode::Subtract, line),
+            BinaryOp::Multiply => self.emit(Opcode::Multiply, line),
+            BinaryOp::Divide => self.emit(Opcode::Divide, line),
+        }
+    }
+
+    /// Get and set instructions for the variable `name`, used at `span`.
+    fn named_variable(&mut self, name: &str, span: Span) -> (Opcode, Opcode) {
+        match self.scope().resolve(name) {
+            Some((slot, initialized)) => {
+                if !initialized {
+                    self.error_at(span, "Can't read local variable in its own initializer.");
+                }
+                (Opcode::GetLocal(slot), Opcode::SetLocal(slot))
+            }
+            None => {
+                let idx = self.make_identifier(name, span);
+                (Opcode::GetGlobal(idx), Opcode::SetGlobal(idx))
+            }
+        }
+    }
+
+    // variables
+    /// Declares `name` in the current scope, returning the constant holding
+    /// its name when it is a global.
+    fn declare_variable(&mut self, name: &'a Ident) -> u8 {
+        if self.scope().depth == 0 {
+            return self.identifier_constant(name);
+        }
+
+        let depth = self.scope().depth;
+        let shadowed = self
+            .scope()
+            .locals
+            .iter()
+            .rev()
+            .take_while(|local| local.depth.is_none_or(|d| d >= depth))
+            .any(|local| local.name == name.name);
+        if shadowed {
+            self.error_at(
+                name.span,
+                "Already a variable with this name in this scope.",
+            );
+        }
+
+        if self.scope().locals.len() == FunctionScope::MAX_LOCALS {
+            self.error_at(name.span, "Too many local variables in function.");
+            return 0;
+        }
+        self.scope().locals.push(Local {
+            name: &name.name,
+            depth: None,
+        });
+        0
+    }
+
+    fn define_variable(&mut self, global: u8, line: usize) {
+        if self.scope().depth > 0 {
+            self.mark_initialized();
+            return;
+        }
+
+        self.emit(Opcode::DefineGlobal(global), line);
+    }
+
+    fn mark_initialized(&mut self) {
+        let scope = self.scope();
+        if scope.depth == 0 {
+            return;
+        }
+
+        let depth = scope.depth;
+        if let Some(local) = scope.locals.last_mut() {
+            local.depth = Some(depth);
+        }
+    }
+
+    fn identifier_constant(&mut self, name: &Ident) -> u8 {
+        self.make_identifier(&name.name, name.span)
+    }
+
+    fn make_identifier(&mut self, name: &str, span: Span) -> u8 {
+        let existing = self
+            .chunk()
+            .constants
+            .iter()
+            .position(|constant| constant.as_str() == Some(name));
+        if let Some(idx) = existing {
+            return idx as u8;
+        }
+
+        match self.heap.alloc_string(name.to_owned()) {
+            Ok(name) => self.make_constant(Value::String(name), span),
+            Err(e) => {
+                self.error_kind_at(span, e.kind(), &e.to_string());
+                0
+            }
+        }
+    }
+
+    // scopes
+    fn scope(&mut self) -> &mut FunctionScope<'a> {
+        self.scopes.last_mut().expect("No active function scope")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.scope().function.chunk
+    }
+
+    fn begin_scope(&mut self) {
+        self.scope().depth += 1;
+    }
+
+    fn end_scope(&mut self, line: usize) {
+        self.scope().depth -= 1;
+
+        loop {
+            let scope = self.scope();
+            match scope.locals.last() {
+                Some(local) if local.depth.is_some_and(|d| d > scope.depth) => {
+                    scope.locals.pop();
+                    self.emit(Opcode::Pop, line);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Line the last character of `span` is on.
+    fn end_line(&self, span: Span) -> usize {
+        span.2 + self.source[&span].matches('\n').count()
+    }
+
+    // emitters
+    fn emit(&mut self, op: Opcode, line: usize) {
+        self.chunk().write(op, line);
+    }
+
+    fn emit_pair(&mut self, op1: Opcode, op2: Opcode, line: usize) {
+        self.emit(op1, line);
+        self.emit(op2, line);
+    }
+
+    fn emit_constant(&mut self, value: Value, span: Span) {
+        let idx = self.make_constant(value, span);
+        self.emit(Opcode::Constant(idx), span.2);
+    }
+
+    fn make_constant(&mut self, value: Value, span: Span) -> u8 {
+        match self.chunk().add_constant(value) {
+            Ok(idx) => idx,
+            Err(_) => {
+                self.error_at(span, "Too many constants in one chunk.");
+                0
+            }
+        }
+    }
+
+    // error
+    fn error_at(&mut self, span: Span, msg: &str) {
+        self.error_kind_at(span, ErrorKind::SyntaxError, msg);
+    }
+
+    fn error_kind_at(&mut self, span: Span, kind: ErrorKind, msg: &str) {
+        let lexeme = format!("'{}'", &self.source[&span]);
+        self.error
+            .get_or_insert_with(|| Compile::new(kind, msg).at(span, &lexeme));
+    }
+}
@@ -0,0 +1,184 @@
+//! Decoding of string literal bodies.
+
+/// A bad escape sequence, as a byte range of the literal's body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeError {
+    pub msg: &'static str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl EscapeError {
+    fn new(msg: &'static str, start: usize, end: usize) -> Self {
+        Self { msg, start, end }
+    }
+}
+
+/// Resolves the escape sequences of a `"..."` body.
+///
+/// Supports `\n \r \t \\ \" \$ \0`, `\xNN` up to `\x7F` and `\u{...}` with one
+/// to six hex digits.
+pub fn unescape(body: &str) -> Result<String, EscapeError> {
+    let mut out = String::with_capacity(body.len());
+    unescape_into(body, 0, &mut out)?;
+    Ok(out)
+}
+
+/// Body of a `"""..."""` string with its indentation stripped.
+///
+/// A blank first line and a blank last line, which hold the delimiters, are
+/// dropped, and the indentation common to every non-blank line is removed.
+/// Escapes are resolved afterwards.
+pub fn text_block(body: &str) -> Result<String, EscapeError> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in body.split('\n') {
+        lines.push((offset, line));
+        offset += line.len() + 1;
+    }
+
+    if lines.len() > 1 && lines[0].1.trim().is_empty() {
+        lines.remove(0);
+    }
+    if lines.len() > 1 && lines[lines.len() - 1].1.trim().is_empty() {
+        lines.pop();
+    }
+
+    // the longest whitespace prefix shared by every non-blank line, compared
+    // char by char so that mixed indentation is never cut mid-character
+    let indent = lines
+        .iter()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(_, line)| &line[..line.len() - line.trim_start().len()])
+        .reduce(|common, ws| {
+            let shared = common
+                .char_indices()
+                .zip(ws.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(common.len().min(ws.len()), |((idx, _), _)| idx);
+            &common[..shared]
+        })
+        .map_or(0, str::len);
+
+    let mut out = String::with_capacity(body.len());
+    for (i, (offset, line)) in lines.into_iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        if !line.trim().is_empty() {
+            unescape_into(&line[indent..], offset + indent, &mut out)?;
+        }
+    }
+    Ok(out)
+}
+
+/// `offset` is where `body` starts in the literal, for error ranges.
+fn unescape_into(body: &str, offset: usize, out: &mut String) -> Result<(), EscapeError> {
+    let mut chars = body.char_indices().peekable();
+
+    while let Some((start, char)) = chars.next() {
+        if char != '\\' {
+            out.push(char);
+            continue;
+        }
+
+        let start = offset + start;
+        let Some((_, escape)) = chars.next() else {
+            return Err(EscapeError::new(
+                "Unfinished escape sequence.",
+                start,
+                start + 1,
+            ));
+        };
+
+        let decoded = match escape {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            '0' => '\0',
+            'x' => {
+                let digits = take(&mut chars, 2, |c| c.is_ascii_hexdigit());
+                let end = start + 2 + digits.len();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte <= 0x7F => byte as char,
+                    Ok(_) if digits.len() == 2 => {
+                        return Err(EscapeError::new(
+                            "Hex escape must be at most \\x7F.",
+                            start,
+                            end,
+                        ))
+                    }
+                    _ => {
+                        return Err(EscapeError::new(
+                            "Hex escape needs two hex digits.",
+                            start,
+                            end,
+                        ))
+                    }
+                }
+            }
+            'u' => {
+                if chars.next_if(|(_, c)| *c == '{').is_none() {
+                    return Err(EscapeError::new(
+                        "Unicode escape must look like \\u{...}.",
+                        start,
+                        start + 2,
+                    ));
+                }
+
+                let digits = take(&mut chars, 6, |c| c.is_ascii_hexdigit());
+                let closed = chars.next_if(|(_, c)| *c == '}').is_some();
+                let end = start + 3 + digits.len() + usize::from(closed);
+                if !closed || digits.is_empty() {
+                    return Err(EscapeError::new(
+                        "Unicode escape must have 1 to 6 hex digits in braces.",
+                        start,
+                        end,
+                    ));
+                }
+
+                match u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                {
+                    Some(char) => char,
+                    None => {
+                        return Err(EscapeError::new(
+                            "Unicode escape is not a valid character.",
+                            start,
+                            end,
+                        ))
+                    }
+                }
+            }
+            other => {
+                return Err(EscapeError::new(
+                    "Unknown escape sequence.",
+                    start,
+                    start + 1 + other.len_utf8(),
+                ))
+            }
+        };
+        out.push(decoded);
+    }
+
+    Ok(())
+}
+
+fn take<I, F>(chars: &mut std::iter::Peekable<I>, max: usize, cond: F) -> String
+where
+    I: Iterator<Item = (usize, char)>,
+    F: Fn(char) -> bool,
+{
+    let mut taken = String::new();
+    while taken.len() < max {
+        match chars.next_if(|(_, c)| cond(*c)) {
+            Some((_, c)) => taken.push(c),
+            None => break,
+        }
+    }
+    taken
+}
@@ -0,0 +1,178 @@
+use crate::Res;
+
+use super::{tokenize, Parser, Token, TokenType};
+
+const INDENT: &str = "    ";
+
+/// Reformats `source`: one statement per line, four spaces per block level,
+/// single spaces around operators and at most one blank line in a row.
+/// Comments are kept; code that does not parse is returned as an error.
+pub fn format(source: &str) -> Res<String> {
+    Parser::new(source).parse()?;
+
+    let mut fmt = Formatter {
+        source,
+        out: String::with_capacity(source.len()),
+        depth: 0,
+        parens: 0,
+        newlines: 0,
+        at_line_start: true,
+        prev: None,
+        prev_unary: false,
+        after_comment: false,
+    };
+    for token in tokenize(source) {
+        fmt.token(token);
+    }
+
+    if !fmt.at_line_start {
+        fmt.out.push('\n');
+    }
+    Ok(fmt.out)
+}
+
+struct Formatter<'src> {
+    source: &'src str,
+    out: String,
+    depth: usize,
+    parens: usize,
+    /// Newlines in the whitespace since the last token.
+    newlines: usize,
+    at_line_start: bool,
+    prev: Option<Token<'src>>,
+    /// Whether `prev` is a prefix `-`.
+    prev_unary: bool,
+    /// Whether the last token written was a `/* */` comment.
+    after_comment: bool,
+}
+
+impl<'src> Formatter<'src> {
+    fn token(&mut self, token: Token<'src>) {
+        let text = token.text(self.source);
+
+        match token.kind {
+            TokenType::Whitespace(ws) => {
+                self.newlines += ws.matches('\n').count();
+                return;
+            }
+            TokenType::Comment(_) | TokenType::DocComment(_) if text.starts_with("//") => {
+                if self.newlines > 0 || self.out.is_empty() {
+                    self.start_line();
+                } else {
+                    // stays on the line it trailed, even if that was ended
+                    if self.at_line_start {
+                        self.out.pop();
+                    }
+                    self.out.push(' ');
+                }
+                self.out.push_str(text);
+                self.end_line();
+                self.newlines = 0;
+                return;
+            }
+            TokenType::CloseBrace => {
+                self.depth = self.depth.saturating_sub(1);
+                self.newlines = 0;
+                if !self.at_line_start {
+                    self.end_line();
+                }
+            }
+            _ => (),
+        }
+
+        if self.at_line_start || (self.after_comment && self.newlines > 0) {
+            self.start_line();
+        } else if self.needs_space(token, text) {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.at_line_start = false;
+        self.newlines = 0;
+
+        match token.kind {
+            TokenType::OpenBrace => {
+                self.depth += 1;
+                self.end_line();
+            }
+            TokenType::CloseBrace => self.end_line(),
+            TokenType::SemiColon if self.parens == 0 => self.end_line(),
+            TokenType::OpenParen => self.parens += 1,
+            TokenType::CloseParen => self.parens = self.parens.saturating_sub(1),
+            _ => (),
+        }
+
+        self.after_comment = matches!(token.kind, TokenType::Comment(_));
+        if !self.after_comment {
+            self.prev_unary = token.kind == TokenType::Minus
+                && !self
+                    .prev
+                    .is_some_and(|prev| ends_operand(prev, self.source));
+            self.prev = Some(token);
+        }
+    }
+
+    /// Indents a new line, keeping one blank line where the source had any.
+    fn start_line(&mut self) {
+        if !self.at_line_start {
+            self.end_line();
+        }
+
+        let after_open = self
+            .prev
+            .is_some_and(|prev| prev.kind == TokenType::OpenBrace);
+        if self.newlines > 1 && !self.out.is_empty() && !after_open {
+            self.out.push('\n');
+        }
+
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.at_line_start = false;
+    }
+
+    fn end_line(&mut self) {
+        self.out.push('\n');
+        self.at_line_start = true;
+    }
+
+    fn needs_space(&self, token: Token, text: &str) -> bool {
+        let Some(prev) = self.prev else {
+            return false;
+        };
+
+        // the rest of an interpolated string after its `}`
+        let closes_interpolation = matches!(
+            token.kind,
+            TokenType::String(_) | TokenType::Interpolation(_)
+        ) && text.starts_with('}');
+
+        let call = token.kind == TokenType::OpenParen && ends_operand(prev, self.source);
+        let tight_after = matches!(
+            prev.kind,
+            TokenType::OpenParen | TokenType::Dot | TokenType::Bang | TokenType::Interpolation(_)
+        );
+        let tight_before = matches!(
+            token.kind,
+            TokenType::Comma | TokenType::SemiColon | TokenType::CloseParen | TokenType::Dot
+        );
+
+        !(call || tight_after || tight_before || closes_interpolation || self.prev_unary)
+    }
+}
+
+/// Whether `token` can end an operand, so that a following `-` is binary
+/// and a following `(` is a call.
+fn ends_operand(token: Token, source: &str) -> bool {
+    match token.kind {
+        TokenType::Ident(_)
+        | TokenType::Number(_)
+        | TokenType::RawString(_)
+        | TokenType::TextBlock(_)
+        | TokenType::Nil
+        | TokenType::True
+        | TokenType::False
+        | TokenType::CloseParen => true,
+        TokenType::String(_) => token.text(source).ends_with('"'),
+        _ => false,
+    }
+}
@@ -8,36 +8,44 @@ pub struct Cursor<'a> {
     chars: Chars<'a>,
     start: usize,
     line: usize,
+    /// Open `{` count of every `${` being lexed, innermost last.
+    interpolations: Vec<usize>,
 }
 
 impl<'a> Cursor<'a> {
-    pub fn new(source: &'a str) -> Cursor {
+    pub fn new(source: &'a str) -> Cursor<'a> {
         let chars = source.chars();
         Self {
             source,
             chars,
             start: 0,
             line: 1,
+            interpolations: Vec::new(),
         }
     }
 
-    // pub fn tokenize(&'a mut self) -> impl Iterator<Item = Token<'_>> + 'a {
-    //     std::iter::from_fn(move || {
-    //         let token = self.advance();
-    //         if token.kind != TokenType::Eof {
-    //             Some(token)
-    //         } else {
-    //             None
-    //         }
-    //     })
-    // }
-
+    /// Next token, skipping whitespace but not comments.
     pub fn advance(&mut self) -> Token<'a> {
-        let start = self.start;
-        let end = self.pos();
+        loop {
+            let token = self.advance_trivia();
+            if !matches!(token.kind, TokenType::Whitespace(_)) {
+                return token;
+            }
+        }
+    }
+
+    /// Next token, whitespace included.
+    pub fn advance_trivia(&mut self) -> Token<'a> {
+        self.reset_ptr();
+
         let line = self.line;
-        let kind = self.next();
-        Token::new(kind, Span::new(start, end, line))
+        let kind = if is_whitespace(self.peek()) {
+            self.bump_while(is_whitespace);
+            TokenType::Whitespace(self.content())
+        } else {
+            self.next()
+        };
+        Token::new(kind, Span::new(self.start, self.pos(), line))
     }
 
     // Misc
@@ -82,6 +90,12 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    fn bump_n(&mut self, n: usize) {
+        for _ in 0..n {
+            self.bump();
+        }
+    }
+
     fn match_char(&mut self, expected: char) -> bool {
         if self.eof() {
             return false;
@@ -97,22 +111,46 @@ impl<'a> Cursor<'a> {
 
     // kind
     fn next(&mut self) -> TokenType<'a> {
-        self.skip_whitespace();
-        self.reset_ptr();
+        if self.eof() {
+            return TokenType::Eof;
+        }
 
         let char = self.bump();
 
         match char {
             '(' => TokenType::OpenParen,
             ')' => TokenType::CloseParen,
-            '{' => TokenType::OpenBrace,
-            '}' => TokenType::CloseBrace,
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                TokenType::OpenBrace
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string_segment()
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    TokenType::CloseBrace
+                }
+                None => TokenType::CloseBrace,
+            },
             ',' => TokenType::Comma,
             '.' => TokenType::Dot,
             '-' => TokenType::Minus,
             '+' => TokenType::Plus,
             ';' => TokenType::SemiColon,
-            '/' => TokenType::Slash,
+            '/' => {
+                if self.match_char('/') {
+                    self.line_comment()
+                } else if self.match_char('*') {
+                    self.block_comment()
+                } else {
+                    TokenType::Slash
+                }
+            }
             '*' => TokenType::Star,
 
             '!' => {
@@ -145,46 +183,167 @@ impl<'a> Cursor<'a> {
             }
 
             '"' => self.string(),
-            '0'..='9' => self.number(),
-            'a'..='z' | 'A'..='Z' | '_' => self.ident(),
+            'r' if matches!(self.peek(), '"' | '#') => self.raw_string(),
+            '0'..='9' => self.number(char),
+            c if is_ident_start(c) => self.ident(),
 
-            '\0' => TokenType::Eof,
             _ => TokenType::Error("Unexpected character."),
         }
     }
 
     // specific handlers
-    fn skip_whitespace(&mut self) {
-        self.bump_while(|x| matches!(x, ' ' | '\r' | '\n' | '\t'))
+    /// `// ...` up to the end of the line, `/// ...` being a doc comment.
+    fn line_comment(&mut self) -> TokenType<'a> {
+        // `////` is a plain comment, like in rust
+        let doc = self.chars.as_str().starts_with('/') && !self.chars.as_str().starts_with("//");
+        self.bump_while(|x| x != '\n');
+
+        if doc {
+            TokenType::DocComment(&self.source[self.start + 3..self.pos()])
+        } else {
+            TokenType::Comment(self.content())
+        }
+    }
+
+    /// `/* ... */`, which may nest.
+    fn block_comment(&mut self) -> TokenType<'a> {
+        let mut depth = 1;
+
+        while depth > 0 {
+            if self.eof() {
+                return TokenType::Error("Unterminated block comment.");
+            }
+
+            match self.bump() {
+                '/' if self.match_char('*') => depth += 1,
+                '*' if self.match_char('/') => depth -= 1,
+                _ => (),
+            }
+        }
+
+        TokenType::Comment(self.content())
     }
 
     fn string(&mut self) -> TokenType<'a> {
-        self.bump_while(|x| x != '"');
+        if self.chars.as_str().starts_with("\"\"") {
+            self.bump();
+            self.bump();
+            return self.text_block();
+        }
 
-        if self.eof() {
-            return TokenType::Error("Unterminated string");
+        self.string_segment()
+    }
+
+    /// Rest of a `"..."` string, after its opening quote or after the `}`
+    /// closing an interpolated expression.
+    ///
+    /// A `${` ends the segment as an `Interpolation`; lexing then carries on
+    /// with the embedded expression until its matching `}`.
+    fn string_segment(&mut self) -> TokenType<'a> {
+        loop {
+            if self.eof() {
+                return TokenType::Error("Unterminated string");
+            }
+
+            match self.bump() {
+                '\\' => {
+                    self.bump();
+                }
+                '"' => return TokenType::String(&self.source[self.start + 1..self.pos() - 1]),
+                '$' if self.match_char('{') => {
+                    self.interpolations.push(0);
+                    return TokenType::Interpolation(&self.source[self.start + 1..self.pos() - 2]);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// `"""..."""`, which may span lines.
+    fn text_block(&mut self) -> TokenType<'a> {
+        while !self.chars.as_str().starts_with("\"\"\"") {
+            if self.eof() {
+                return TokenType::Error("Unterminated string");
+            }
+            if self.bump() == '\\' {
+                self.bump();
+            }
         }
 
-        self.bump();
+        let end = self.pos();
+        self.bump_n(3);
+        TokenType::TextBlock(&self.source[self.start + 3..end])
+    }
+
+    /// `r"..."` or `r#"..."#` with any number of hashes; escapes are not
+    /// processed.
+    fn raw_string(&mut self) -> TokenType<'a> {
+        let mut hashes = 0;
+        while self.match_char('#') {
+            hashes += 1;
+        }
+        if !self.match_char('"') {
+            return TokenType::Error("Expected '\"' to start a raw string.");
+        }
 
-        let end = self.pos() - 1;
-        let content = &self.source[self.start + 1..end];
+        let body = self.pos();
+        loop {
+            if self.eof() {
+                return TokenType::Error("Unterminated raw string.");
+            }
 
-        TokenType::String(content)
+            let end = self.pos();
+            if self.bump() == '"'
+                && self
+                    .chars
+                    .as_str()
+                    .bytes()
+                    .take_while(|b| *b == b'#')
+                    .count()
+                    >= hashes
+            {
+                self.bump_n(hashes);
+                return TokenType::RawString(&self.source[body..end]);
+            }
+        }
     }
 
-    fn number(&mut self) -> TokenType<'a> {
-        self.bump_while(|x| x.is_ascii_digit());
+    /// Decimal ints and floats, or `0x`, `0o` and `0b` ints, all of them
+    /// allowing `_` separators. Values are checked by `Token::object`.
+    fn number(&mut self, first: char) -> TokenType<'a> {
+        if first == '0' && matches!(self.peek(), 'x' | 'o' | 'b' | 'X' | 'O' | 'B') {
+            self.bump_while(|x| x.is_ascii_alphanumeric() || x == '_');
+            return TokenType::Number(self.content());
+        }
+
+        self.bump_while(|x| x.is_ascii_digit() || x == '_');
+
+        // `1.` on its own stays an int followed by a dot
+        let mut rest = self.chars.as_str().chars();
+        if rest.next() == Some('.') && rest.next().is_some_and(|x| x.is_ascii_digit()) {
+            self.bump();
+            self.bump_while(|x| x.is_ascii_digit() || x == '_');
+        }
 
-        if self.match_char('.') {
-            self.bump_while(|x| x.is_ascii_digit());
+        if matches!(self.peek(), 'e' | 'E') {
+            let mut rest = self.chars.as_str()[1..].chars();
+            let digit = match rest.next() {
+                Some('+' | '-') => rest.next(),
+                next => next,
+            };
+            if digit.is_some_and(|x| x.is_ascii_digit()) {
+                self.bump();
+                self.match_char('+');
+                self.match_char('-');
+                self.bump_while(|x| x.is_ascii_digit() || x == '_');
+            }
         }
 
         TokenType::Number(self.content())
     }
 
     fn ident(&mut self) -> TokenType<'a> {
-        self.bump_while(|x| x.is_ascii_alphabetic());
+        self.bump_while(is_ident_continue);
         let symbol = self.content();
 
         match symbol {
@@ -207,3 +366,30 @@ impl<'a> Cursor<'a> {
         }
     }
 }
+
+/// Every token of `source`, trivia included, without the final `Eof`.
+///
+/// Tokens are contiguous: joining `token.text(source)` for all of them gives
+/// back `source` exactly, which makes this suitable for highlighters and
+/// formatters. Lexing errors show up as `Error` tokens covering the offending
+/// text.
+pub fn tokenize(source: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut cursor = Cursor::new(source);
+    std::iter::from_fn(move || {
+        let token = cursor.advance_trivia();
+        (token.kind != TokenType::Eof).then_some(token)
+    })
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\r' | '\n' | '\t')
+}
+
+/// Unicode identifiers: `_` or `XID_Start`, then `XID_Continue`, as in Rust.
+fn is_ident_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+fn is_ident_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
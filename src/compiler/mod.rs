@@ -1,8 +1,16 @@
+pub mod ast;
+mod codegen;
+mod escape;
+mod format;
 mod lexer;
 mod parse_rule;
 mod parser;
 mod precedence;
+mod scope;
 mod span;
 mod token;
 
-pub use self::{lexer::*, parse_rule::*, parser::*, precedence::*, span::*, token::*};
+pub use self::{
+    codegen::*, escape::*, format::*, lexer::*, parse_rule::*, parser::*, precedence::*, scope::*,
+    span::*, token::*,
+};
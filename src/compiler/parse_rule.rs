@@ -1,13 +1,17 @@
-use crate::{chunks::Opcode, object::ObjRef, value::Value};
+use crate::{error::RxError, value::Value};
 
-use super::{Parser, Precedence, TokenType};
+use super::{
+    ast::{BinaryOp, Expr, ExprKind, Literal, UnaryOp},
+    text_block, unescape, Parser, Precedence, Token, TokenType,
+};
 
-pub type ParseFn<'vm> = fn(&mut Parser<'vm>);
+pub type PrefixFn<'vm> = fn(&mut Parser<'vm>, bool) -> Expr;
+pub type InfixFn<'vm> = fn(&mut Parser<'vm>, Expr, bool) -> Expr;
 
 #[derive(Clone, Copy, Default)]
 pub struct ParseRule<'parse> {
-    pub prefix: Option<ParseFn<'parse>>,
-    pub infix: Option<ParseFn<'parse>>,
+    pub prefix: Option<PrefixFn<'parse>>,
+    pub infix: Option<InfixFn<'parse>>,
     pub precedence: Precedence,
 }
 
@@ -16,6 +20,12 @@ impl<'parse> ParseRule<'parse> {
         match kind {
             TokenType::OpenParen => ParseRule {
                 prefix: Some(grouping),
+                infix: Some(call),
+                precedence: Precedence::Call,
+            },
+            TokenType::Dot => ParseRule {
+                infix: Some(dot),
+                precedence: Precedence::Call,
                 ..Default::default()
             },
             TokenType::Minus => ParseRule {
@@ -55,7 +65,15 @@ impl<'parse> ParseRule<'parse> {
                 precedence: Precedence::Comparison,
                 ..Default::default()
             },
-            TokenType::String(_) => ParseRule {
+            TokenType::Ident(_) => ParseRule {
+                prefix: Some(variable),
+                ..Default::default()
+            },
+            TokenType::Interpolation(_) => ParseRule {
+                prefix: Some(interpolation),
+                ..Default::default()
+            },
+            TokenType::String(_) | TokenType::RawString(_) | TokenType::TextBlock(_) => ParseRule {
                 prefix: Some(string),
                 ..Default::default()
             },
@@ -74,66 +92,182 @@ impl<'parse> ParseRule<'parse> {
     }
 }
 
-fn grouping(parser: &mut Parser<'_>) {
-    parser.expression();
+fn grouping(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    let start = parser.previous.span;
+    let expr = parser.expression();
     parser.consume(TokenType::CloseParen, "Expect ')' after expression.");
+
+    Expr::new(ExprKind::Grouping(Box::new(expr)), parser.span_from(start))
 }
 
-fn binary(parser: &mut Parser<'_>) {
+fn binary(parser: &mut Parser<'_>, lhs: Expr, _can_assign: bool) -> Expr {
     let op = parser.previous.kind;
 
     let rule = ParseRule::get_rule(op);
-    parser.parse_precedence(rule.precedence.next());
-
-    match op {
-        TokenType::BangEqual => parser.emit_bytes(Opcode::Equal, Opcode::Not),
-        TokenType::EqualEqual => parser.emit_byte(Opcode::Equal),
-        TokenType::Greater => parser.emit_byte(Opcode::Greater),
-        TokenType::GreaterEqual => parser.emit_bytes(Opcode::Less, Opcode::Not),
-        TokenType::Less => parser.emit_byte(Opcode::Less),
-        TokenType::LessEqual => parser.emit_bytes(Opcode::Greater, Opcode::Not),
-        TokenType::Plus => parser.emit_byte(Opcode::Add),
-        TokenType::Minus => parser.emit_byte(Opcode::Subtract),
-        TokenType::Star => parser.emit_byte(Opcode::Multiply),
-        TokenType::Slash => parser.emit_byte(Opcode::Divide),
-        _ => (),
-    }
+    let rhs = parser.parse_precedence(rule.precedence.next());
+
+    let op = match op {
+        TokenType::BangEqual => BinaryOp::NotEqual,
+        TokenType::EqualEqual => BinaryOp::Equal,
+        TokenType::Greater => BinaryOp::Greater,
+        TokenType::GreaterEqual => BinaryOp::GreaterEqual,
+        TokenType::Less => BinaryOp::Less,
+        TokenType::LessEqual => BinaryOp::LessEqual,
+        TokenType::Plus => BinaryOp::Add,
+        TokenType::Minus => BinaryOp::Subtract,
+        TokenType::Star => BinaryOp::Multiply,
+        TokenType::Slash => BinaryOp::Divide,
+        _ => unreachable!("no binary rule for {op:?}"),
+    };
+
+    let span = parser.span_from(lhs.span);
+    let kind = ExprKind::Binary {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+    };
+    Expr::new(kind, span)
 }
 
-fn unary(parser: &mut Parser<'_>) {
-    let operator = parser.previous.kind;
+fn unary(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    let operator = parser.previous;
 
-    parser.parse_precedence(Precedence::Unary);
+    let expr = parser.parse_precedence(Precedence::Unary);
 
-    match operator {
-        TokenType::Minus => parser.emit_byte(Opcode::Negate),
-        TokenType::Bang => parser.emit_byte(Opcode::Not),
-        _ => (),
-    }
+    let op = match operator.kind {
+        TokenType::Minus => UnaryOp::Negate,
+        TokenType::Bang => UnaryOp::Not,
+        kind => unreachable!("no unary rule for {kind:?}"),
+    };
+    let kind = ExprKind::Unary {
+        op,
+        expr: Box::new(expr),
+    };
+    Expr::new(kind, parser.span_from(operator.span))
 }
 
-fn number(parser: &mut Parser<'_>) {
-    let value = parser
-        .previous
-        .object()
-        .map_err(|x| parser.error(&x.to_string()))
-        .unwrap();
-    parser.emit_constant(value);
+fn number(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    let literal = match parser.previous.object() {
+        Ok(Value::Int(int)) => Literal::Int(int),
+        Ok(Value::Float(float)) => Literal::Float(float),
+        Ok(_) => Literal::Nil,
+        Err(RxError::Compile(e)) => {
+            parser.error(e.message());
+            Literal::Nil
+        }
+        Err(e) => {
+            parser.error(&e.to_string());
+            Literal::Nil
+        }
+    };
+
+    Expr::new(ExprKind::Literal(literal), parser.previous.span)
 }
 
-fn literal(parser: &mut Parser<'_>) {
-    match parser.previous.kind {
-        TokenType::Nil => parser.emit_byte(Opcode::Nil),
-        TokenType::True => parser.emit_byte(Opcode::True),
-        TokenType::False => parser.emit_byte(Opcode::False),
-        _ => (),
+fn literal(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    let literal = match parser.previous.kind {
+        TokenType::True => Literal::Bool(true),
+        TokenType::False => Literal::Bool(false),
+        _ => Literal::Nil,
+    };
+
+    Expr::new(ExprKind::Literal(literal), parser.previous.span)
+}
+
+fn string(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    string_literal(parser, parser.previous)
+}
+
+/// `"a ${b} c"`: every non-empty segment and every expression becomes a
+/// part, in source order.
+fn interpolation(parser: &mut Parser<'_>, _can_assign: bool) -> Expr {
+    let start = parser.previous.span;
+    let mut parts = Vec::new();
+
+    loop {
+        let segment = parser.previous;
+        if !segment.lexeme().is_empty() {
+            parts.push(string_literal(parser, segment));
+        }
+
+        parts.push(parser.expression());
+
+        if !parser.match_token(TokenType::Interpolation("")) {
+            break;
+        }
+    }
+
+    parser.consume(
+        TokenType::String(""),
+        "Expect '}' after interpolated expression.",
+    );
+    if matches!(parser.previous.kind, TokenType::String(tail) if !tail.is_empty()) {
+        parts.push(string_literal(parser, parser.previous));
     }
+
+    Expr::new(ExprKind::Interpolation(parts), parser.span_from(start))
+}
+
+fn string_literal<'a>(parser: &mut Parser<'a>, token: Token<'a>) -> Expr {
+    let (decoded, quote) = match token.kind {
+        TokenType::String(body) | TokenType::Interpolation(body) => (unescape(body), 1),
+        TokenType::TextBlock(body) => (text_block(body), 3),
+        TokenType::RawString(body) => (Ok(body.to_owned()), 0),
+        kind => unreachable!("{kind:?} is not a string"),
+    };
+
+    let string = decoded.unwrap_or_else(|e| {
+        parser.escape_error(token, quote, e);
+        String::new()
+    });
+    Expr::new(ExprKind::Literal(Literal::String(string)), token.span)
 }
 
-fn string(parser: &mut Parser<'_>) {
-    let string = parser.previous;
-    if let TokenType::String(v) = string.kind {
-        let s = Value::String(ObjRef::new(Box::into_raw(Box::new(v.to_string()))));
-        parser.emit_constant(s);
+fn variable(parser: &mut Parser<'_>, can_assign: bool) -> Expr {
+    let start = parser.previous.span;
+    let name = parser.previous.lexeme().to_owned();
+
+    if can_assign && parser.match_token(TokenType::Equal) {
+        let value = parser.expression();
+        let kind = ExprKind::Assign {
+            name,
+            value: Box::new(value),
+        };
+        Expr::new(kind, parser.span_from(start))
+    } else {
+        Expr::new(ExprKind::Variable(name), start)
     }
 }
+
+fn call(parser: &mut Parser<'_>, callee: Expr, _can_assign: bool) -> Expr {
+    let args = parser.argument_list();
+
+    let span = parser.span_from(callee.span);
+    let kind = ExprKind::Call {
+        callee: Box::new(callee),
+        args,
+    };
+    Expr::new(kind, span)
+}
+
+fn dot(parser: &mut Parser<'_>, object: Expr, can_assign: bool) -> Expr {
+    let start = object.span;
+    let name = parser.ident("Expect property name after '.'.");
+    let object = Box::new(object);
+
+    let kind = if can_assign && parser.match_token(TokenType::Equal) {
+        let value = Box::new(parser.expression());
+        ExprKind::Set {
+            object,
+            name,
+            value,
+        }
+    } else if parser.match_token(TokenType::OpenParen) {
+        let args = parser.argument_list();
+        ExprKind::Invoke { object, name, args }
+    } else {
+        ExprKind::Get { object, name }
+    };
+
+    Expr::new(kind, parser.span_from(start))
+}
@@ -1,50 +1,58 @@
 use crate::{
-    chunks::{Chunk, Opcode},
-    value::Value,
+    error::{Compile, ErrorKind, RxError},
+    Res,
 };
 
-use super::{Cursor, ParseRule, Precedence, Token, TokenType};
+use super::{
+    ast::{Expr, ExprKind, FnDecl, Ident, Literal, Script, Stmt, StmtKind},
+    Cursor, EscapeError, ParseRule, Precedence, Span, Token, TokenType,
+};
 
 pub struct Parser<'src> {
     pub(super) cursor: Cursor<'src>,
     pub(super) current: Token<'src>,
     pub(super) previous: Token<'src>,
 
-    pub(super) chunk: &'src mut Chunk,
+    /// Nesting of blocks and function bodies around the current token.
+    depth: usize,
+    result: Option<Expr>,
 
-    pub(super) had_error: bool,
+    pub(super) error: Option<Compile>,
     panic_mode: bool,
 }
 
 impl<'src> Parser<'src> {
-    pub fn new(content: &'src str, chunk: &'src mut Chunk) -> Self {
+    pub fn new(content: &'src str) -> Self {
         Self {
             cursor: Cursor::new(content),
             current: Token::default(),
             previous: Token::default(),
 
-            chunk,
+            depth: 0,
+            result: None,
 
-            had_error: false,
+            error: None,
             panic_mode: false,
         }
     }
 
     // pub api
-    pub fn compile(mut self) {
-        self.advance();
-        self.expression();
-        self.consume(TokenType::Eof, "Expect end of expression");
+    pub fn parse(mut self) -> Res<Script> {
+        let mut body = Vec::new();
 
-        self.emit_return();
+        self.advance();
+        while !self.match_token(TokenType::Eof) {
+            body.extend(self.declaration());
+        }
 
-        #[cfg(feature = "trace")]
-        {
-            if !self.had_error {
-                let dis = crate::chunks::Disassembler::new(&self.chunk, None);
-                dis.disassemble("code");
-            }
+        if let Some(error) = self.error {
+            return Err(RxError::new(error));
         }
+
+        Ok(Script {
+            body,
+            result: self.result,
+        })
     }
 
     // main logic
@@ -56,35 +64,174 @@ impl<'src> Parser<'src> {
 
             if self.current.kind == TokenType::Error("") {
                 self.error_at_current(self.current.lexeme());
-            } else {
+            } else if !self.current.kind.is_trivia() {
                 break;
             }
         }
     }
 
+    // declarations
+    fn declaration(&mut self) -> Option<Stmt> {
+        let start = self.current.span;
+        let kind = if self.match_token(TokenType::Fn) {
+            Some(StmtKind::Fn(self.function()))
+        } else if self.match_token(TokenType::Var) {
+            Some(self.var_declaration())
+        } else {
+            self.statement()
+        };
+
+        if self.panic_mode {
+            self.synchronize();
+        }
+        kind.map(|kind| Stmt::new(kind, self.span_from(start)))
+    }
+
+    fn function(&mut self) -> FnDecl {
+        let name = self.ident("Expect function name.");
+
+        let mut params = Vec::new();
+        self.consume(TokenType::OpenParen, "Expect '(' after function name.");
+        if !self.check(TokenType::CloseParen) {
+            loop {
+                if params.len() == u8::MAX as usize {
+                    self.error_at_current("Can't have more than 255 parameters.");
+                }
+                params.push(self.ident("Expect parameter name."));
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::CloseParen, "Expect ')' after parameters.");
+        self.consume(TokenType::OpenBrace, "Expect '{' before function body.");
+        let body = self.block();
+
+        FnDecl { name, params, body }
+    }
+
+    fn var_declaration(&mut self) -> StmtKind {
+        let name = self.ident("Expect variable name.");
+
+        let init = self
+            .match_token(TokenType::Equal)
+            .then(|| self.expression());
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after variable declaration.",
+        );
+
+        StmtKind::Var { name, init }
+    }
+
+    // statements
+    fn statement(&mut self) -> Option<StmtKind> {
+        if self.match_token(TokenType::Return) {
+            Some(self.return_statement())
+        } else if self.match_token(TokenType::OpenBrace) {
+            Some(StmtKind::Block(self.block()))
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn block(&mut self) -> Vec<Stmt> {
+        let mut body = Vec::new();
+
+        self.depth += 1;
+        while !self.check(TokenType::CloseBrace) && !self.check(TokenType::Eof) {
+            body.extend(self.declaration());
+        }
+        self.depth -= 1;
+
+        self.consume(TokenType::CloseBrace, "Expect '}' after block.");
+        body
+    }
+
+    fn return_statement(&mut self) -> StmtKind {
+        if self.match_token(TokenType::SemiColon) {
+            return StmtKind::Return(None);
+        }
+
+        let value = self.expression();
+        self.consume(TokenType::SemiColon, "Expect ';' after return value.");
+        StmtKind::Return(Some(value))
+    }
+
+    fn expression_statement(&mut self) -> Option<StmtKind> {
+        let expr = self.expression();
+
+        // a trailing expression without ';' is the result of the script
+        if self.check(TokenType::Eof) && self.depth == 0 {
+            self.result = Some(expr);
+            return None;
+        }
+
+        self.consume(TokenType::SemiColon, "Expect ';' after expression.");
+        Some(StmtKind::Expr(expr))
+    }
+
     // parse expression
-    pub(super) fn expression(&mut self) {
-        self.parse_precedence(Precedence::Assignment);
+    pub(super) fn expression(&mut self) -> Expr {
+        self.parse_precedence(Precedence::Assignment)
     }
 
-    pub(super) fn parse_precedence(&mut self, precedence: Precedence) {
+    pub(super) fn parse_precedence(&mut self, precedence: Precedence) -> Expr {
         self.advance();
 
+        let can_assign = precedence <= Precedence::Assignment;
         let rule = ParseRule::get_rule(self.previous.kind).prefix;
-        if let Some(prefix) = rule {
-            prefix(self)
-        } else {
+        let Some(prefix) = rule else {
             self.error("Expect expression.");
-            return;
-        }
+            return Expr::new(ExprKind::Literal(Literal::Nil), self.previous.span);
+        };
+        let mut expr = prefix(self, can_assign);
 
         while precedence <= ParseRule::get_rule(self.current.kind).precedence {
             self.advance();
             let infix = ParseRule::get_rule(self.previous.kind).infix;
             if let Some(infix_rule) = infix {
-                infix_rule(self)
+                expr = infix_rule(self, expr, can_assign);
             }
         }
+
+        if can_assign && self.match_token(TokenType::Equal) {
+            self.error("Invalid assignment target.");
+        }
+        expr
+    }
+
+    pub(super) fn argument_list(&mut self) -> Vec<Expr> {
+        let mut args = Vec::new();
+        if !self.check(TokenType::CloseParen) {
+            loop {
+                let arg = self.expression();
+                if args.len() == u8::MAX as usize {
+                    self.error("Can't have more than 255 arguments.");
+                }
+                args.push(arg);
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::CloseParen, "Expect ')' after arguments.");
+        args
+    }
+
+    pub(super) fn ident(&mut self, msg: &str) -> Ident {
+        self.consume(TokenType::Ident(""), msg);
+        Ident {
+            name: self.previous.lexeme().to_owned(),
+            span: self.previous.span,
+        }
+    }
+
+    /// Span from the token at `start` up to the last consumed token.
+    pub(super) fn span_from(&self, start: Span) -> Span {
+        start.combine(&self.previous.span)
     }
 
     // misc
@@ -97,26 +244,40 @@ impl<'src> Parser<'src> {
         self.error_at_current(msg)
     }
 
-    // emitters
-    pub(super) fn emit_byte(&mut self, byte: Opcode) {
-        self.chunk.write(byte, self.previous.span.2);
+    pub(super) fn check(&self, ty: TokenType) -> bool {
+        self.current.kind == ty
     }
 
-    pub(super) fn emit_bytes(&mut self, byte1: Opcode, byte2: Opcode) {
-        self.chunk.write(byte1, self.previous.span.2);
-        self.chunk.write(byte2, self.previous.span.2);
-    }
+    pub(super) fn match_token(&mut self, ty: TokenType) -> bool {
+        if !self.check(ty) {
+            return false;
+        }
 
-    pub(super) fn emit_return(&mut self) {
-        self.emit_byte(Opcode::Return);
+        self.advance();
+        true
     }
 
-    pub(super) fn emit_constant(&mut self, value: Value) {
-        let idx = self.chunk.add_constant(value);
-        self.emit_byte(Opcode::Constant(idx));
+    fn synchronize(&mut self) {
+        self.panic_mode = false;
+
+        while !self.check(TokenType::Eof) {
+            if self.previous.kind == TokenType::SemiColon {
+                return;
+            }
+
+            match self.current.kind {
+                TokenType::Class
+                | TokenType::Fn
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Return => return,
+                _ => self.advance(),
+            }
+        }
     }
 
-    // expr
     // error
     pub(super) fn error_at_current(&mut self, msg: &str) {
         self.error_at(self.current, msg);
@@ -127,19 +288,34 @@ impl<'src> Parser<'src> {
     }
 
     pub(super) fn error_at(&mut self, token: Token, msg: &str) {
+        self.error_kind_at(token, ErrorKind::SyntaxError, msg);
+    }
+
+    /// Points at the bad escape inside the string literal `token`, whose
+    /// body starts after `quote` bytes.
+    pub(super) fn escape_error(&mut self, token: Token<'src>, quote: usize, e: EscapeError) {
+        let body = token.lexeme();
+        let line = token.line() + body[..e.start].matches('\n').count();
+        let start = token.span.0 + quote + e.start;
+        let span = Span::new(start, start + e.end - e.start, line);
+
+        let escape = Token::new(TokenType::String(&body[e.start..e.end]), span);
+        self.error_at(escape, e.msg);
+    }
+
+    pub(super) fn error_kind_at(&mut self, token: Token, kind: ErrorKind, msg: &str) {
         if self.panic_mode {
             return;
         }
 
         self.panic_mode = true;
-        self.had_error = true;
 
-        print!("[line {}] Error", token.line());
-        match token.kind {
-            TokenType::Eof => print!(" at end"),
-            TokenType::Error(_) => {}
-            x => print!(" at '{}'", x.as_str()),
-        }
-        println!(": {}\n", msg);
+        let lexeme = match token.kind {
+            TokenType::Eof => "end".to_owned(),
+            TokenType::Error(_) => String::new(),
+            x => format!("'{}'", x.as_str()),
+        };
+        self.error
+            .get_or_insert(Compile::new(kind, msg).at(token.span, &lexeme));
     }
 }
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use crate::object::Function;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    Script,
+    Function,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Local<'src> {
+    pub name: &'src str,
+    /// `None` until the initializer has been compiled.
+    pub depth: Option<usize>,
+}
+
+/// Compilation state of the function currently being emitted.
+#[derive(Debug)]
+pub struct FunctionScope<'src> {
+    pub kind: FunctionKind,
+    pub function: Function,
+    pub locals: Vec<Local<'src>>,
+    pub depth: usize,
+}
+
+impl<'src> FunctionScope<'src> {
+    pub const MAX_LOCALS: usize = u8::MAX as usize + 1;
+
+    pub fn new(kind: FunctionKind, name: Arc<str>, file: Arc<str>) -> Self {
+        Self {
+            kind,
+            function: Function::new(name, file),
+            // slot zero holds the function being called
+            locals: vec![Local {
+                name: "",
+                depth: Some(0),
+            }],
+            depth: 0,
+        }
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<(u8, bool)> {
+        self.locals
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, local)| local.name == name)
+            .map(|(slot, local)| (slot as u8, local.depth.is_some()))
+    }
+}
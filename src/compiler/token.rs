@@ -1,7 +1,7 @@
 use std::fmt;
 
 use crate::{
-    error::{Compile, RxError},
+    error::{Compile, ErrorKind, RxError},
     value::Value,
     Res,
 };
@@ -25,14 +25,12 @@ impl<'t> Token<'t> {
             TokenType::False => Ok(Value::Bool(false)),
             // TokenType::Nil => Ok(Value::Nil),
             // TokenType::String(s) => Ok(Value::String(s.into_string())),
-            TokenType::Number(num) => match num.parse::<i64>() {
-                Ok(v) => Ok(Value::Int(v)),
-                Err(_) => match num.parse::<f64>() {
-                    Ok(f) => Ok(Value::Float(f)),
-                    Err(er) => Err(RxError::new(Compile::new(&er.to_string()))),
-                },
-            },
-            _ => todo!(),
+            TokenType::Number(num) => parse_number(num)
+                .map_err(|msg| RxError::new(Compile::new(ErrorKind::SyntaxError, msg))),
+            _ => Err(RxError::new(Compile::new(
+                ErrorKind::SyntaxError,
+                &format!("Expect a literal, found '{}'.", self.lexeme()),
+            ))),
         }
     }
 
@@ -40,11 +38,52 @@ impl<'t> Token<'t> {
         self.span.2
     }
 
+    /// Exact text of the token in the `source` it was lexed from.
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[&self.span]
+    }
+
     pub fn lexeme(&self) -> &'t str {
         self.kind.as_str()
     }
 }
 
+fn parse_number(num: &str) -> Result<Value, &'static str> {
+    let digits = num.replace('_', "");
+
+    let radix = match digits.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+
+    if radix != 10 {
+        let digits = &digits[2..];
+        if digits.is_empty() {
+            return Err("Missing digits after the integer base prefix.");
+        }
+        if !digits.chars().all(|x| x.is_digit(radix)) {
+            return Err("Invalid digit for the integer base.");
+        }
+        return i64::from_str_radix(digits, radix)
+            .map(Value::Int)
+            .map_err(|_| "Integer literal too large.");
+    }
+
+    if digits.contains(['.', 'e', 'E']) {
+        return digits
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| "Invalid float literal.");
+    }
+
+    digits
+        .parse::<i64>()
+        .map(Value::Int)
+        .map_err(|_| "Integer literal too large.")
+}
+
 #[macro_export]
 macro_rules! token {
     ($kind:ident, $span: expr) => {
@@ -98,7 +137,18 @@ pub enum TokenType<'str> {
     LessEqual,
 
     Ident(&'str str),
+    /// Body of a `"..."` string, escapes still unresolved.
     String(&'str str),
+    /// Segment of a string up to a `${`, escapes still unresolved.
+    ///
+    /// The embedded expression follows, then further segments, and a
+    /// `String` holds the rest after the last `}`.
+    Interpolation(&'str str),
+    /// Body of a `r"..."` or `r#"..."#` string.
+    RawString(&'str str),
+    /// Body of a `"""..."""` string, indentation and escapes still
+    /// unresolved.
+    TextBlock(&'str str),
     Number(&'str str),
 
     And,
@@ -117,6 +167,13 @@ pub enum TokenType<'str> {
     Var,
     While,
 
+    /// Run of blanks and newlines, only produced for tooling.
+    Whitespace(&'str str),
+    /// `//` and `/* */` comments, kept so tooling can see them.
+    Comment(&'str str),
+    /// Text of a `///` comment, without the slashes.
+    DocComment(&'str str),
+
     Error(&'str str),
     #[default]
     Eof,
@@ -159,9 +216,15 @@ impl PartialEq for TokenType<'_> {
                 | (Self::True, Self::True)
                 | (Self::Var, Self::Var)
                 | (Self::While, Self::While)
+                | (Self::Whitespace(_), Self::Whitespace(_))
+                | (Self::Comment(_), Self::Comment(_))
+                | (Self::DocComment(_), Self::DocComment(_))
                 | (Self::Error(_), Self::Error(_))
                 | (Self::Eof, Self::Eof)
                 | (Self::String(_), Self::String(_))
+                | (Self::Interpolation(_), Self::Interpolation(_))
+                | (Self::RawString(_), Self::RawString(_))
+                | (Self::TextBlock(_), Self::TextBlock(_))
                 | (Self::Ident(_), Self::Ident(_))
                 | (Self::Number(_), Self::Number(_))
         )
@@ -187,6 +250,14 @@ macro_rules! as_str {
 }
 
 impl<'str> TokenType<'str> {
+    /// Whether the parser skips this token.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace(_) | Self::Comment(_) | Self::DocComment(_)
+        )
+    }
+
     pub fn as_str(&self) -> &'str str {
         as_str!(s self, [
                 OpenParen => "(",
@@ -210,6 +281,9 @@ impl<'str> TokenType<'str> {
                 LessEqual => "<=",
                 Ident(data),
                 String(data),
+                Interpolation(data),
+                RawString(data),
+                TextBlock(data),
                 Number(data),
                 And => "and",
                 Class => "class",
@@ -226,6 +300,9 @@ impl<'str> TokenType<'str> {
                 True => "true",
                 Var => "var",
                 While => "while",
+                Whitespace(data),
+                Comment(data),
+                DocComment(data),
                 Error(data),
                 Eof => "<<EOF>>",
         ])
@@ -258,6 +335,9 @@ impl fmt::Display for TokenType<'_> {
                 LessEqual => "<=",
                 Ident(data),
                 String(data),
+                Interpolation(data),
+                RawString(data),
+                TextBlock(data),
                 Number(data),
                 And => "and",
                 Class => "class",
@@ -274,6 +354,9 @@ impl fmt::Display for TokenType<'_> {
                 True => "true",
                 Var => "var",
                 While => "while",
+                Whitespace(data),
+                Comment(data),
+                DocComment(data),
                 Error(data),
                 Eof => "<<EOF>>",
             ]
@@ -0,0 +1,295 @@
+//! Conversions between `Value` and Rust types.
+//!
+//! `IntoValue` may allocate on the VM heap, so it takes the `Vm`;
+//! `FromValue` only reads and fails with a `TypeError` naming the expected
+//! and the actual type.
+
+use std::collections::HashMap;
+
+use crate::{
+    error::{ErrorKind, Runtime, RxError},
+    value::Value,
+    vm::Vm,
+    Res,
+};
+
+pub trait IntoValue {
+    fn into_value(self, vm: &mut Vm) -> Res<Value>;
+}
+
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Res<Self>;
+}
+
+/// A Rust function that can be registered as a native with
+/// `Vm::register_fn`.
+///
+/// Implemented for `Fn(A, B, ..) -> R` with up to six arguments, where every
+/// argument is `FromValue` and `R` is `IntoValue`. `Args` only exists to keep
+/// the implementations apart.
+pub trait NativeSignature<Args> {
+    fn arity() -> u8;
+
+    fn invoke(&self, vm: &mut Vm, args: &[Value]) -> Res<Value>;
+}
+
+/// Error for a value of the wrong type.
+pub fn type_mismatch(expected: &str, value: &Value) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::TypeError,
+        &format!("Expected {expected} but got {}.", value.get_ty()),
+    ))
+}
+
+fn out_of_range(ty: &str, value: impl std::fmt::Display) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::ArithmeticError,
+        &format!("Integer {value} does not fit in {ty}."),
+    ))
+}
+
+impl IntoValue for Value {
+    fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+        Ok(self)
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Res<Self> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+        Ok(Value::Nil)
+    }
+}
+
+impl<T: IntoValue> IntoValue for Res<T> {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        self?.into_value(vm)
+    }
+}
+
+macro_rules! int_conversions {
+    ($($ty:ty),*) => {$(
+        impl IntoValue for $ty {
+            fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+                i64::try_from(self)
+                    .map(Value::Int)
+                    .map_err(|_| out_of_range("int64", self))
+            }
+        }
+
+        impl FromValue for $ty {
+            fn from_value(value: Value) -> Res<Self> {
+                match value {
+                    Value::Int(i) => {
+                        <$ty>::try_from(i).map_err(|_| out_of_range(stringify!($ty), i))
+                    }
+                    _ => Err(type_mismatch("int64", &value)),
+                }
+            }
+        }
+    )*};
+}
+
+int_conversions!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl IntoValue for f64 {
+    fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+        Ok(Value::Float(self))
+    }
+}
+
+impl FromValue for f64 {
+    /// Ints are widened, like they are in arithmetic.
+    fn from_value(value: Value) -> Res<Self> {
+        value
+            .as_number()
+            .ok_or_else(|| type_mismatch("float64", &value))
+    }
+}
+
+impl IntoValue for f32 {
+    fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+        Ok(Value::Float(self.into()))
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: Value) -> Res<Self> {
+        f64::from_value(value).map(|f| f as f32)
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self, _vm: &mut Vm) -> Res<Value> {
+        Ok(Value::Bool(self))
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Res<Self> {
+        value.as_bool().ok_or_else(|| type_mismatch("bool", &value))
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        vm.new_string(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        vm.new_string(&self)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Res<Self> {
+        value
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| type_mismatch("string", &value))
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        match self {
+            Some(value) => value.into_value(vm),
+            None => Ok(Value::Nil),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Res<Self> {
+        match value {
+            Value::Nil => Ok(None),
+            _ => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        let items = self
+            .into_iter()
+            .map(|item| item.into_value(vm))
+            .collect::<Res<Vec<_>>>()?;
+        vm.new_list(items)
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Res<Self> {
+        match value {
+            Value::List(list) => list
+                .get()
+                .items
+                .iter()
+                .map(|v| T::from_value(v.clone()))
+                .collect(),
+            _ => Err(type_mismatch("list", &value)),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for HashMap<String, T> {
+    fn into_value(self, vm: &mut Vm) -> Res<Value> {
+        let entries = self
+            .into_iter()
+            .map(|(key, value)| Ok((key, value.into_value(vm)?)))
+            .collect::<Res<HashMap<_, _>>>()?;
+        vm.new_map(entries)
+    }
+}
+
+impl<T: FromValue> FromValue for HashMap<String, T> {
+    fn from_value(value: Value) -> Res<Self> {
+        match value {
+            Value::Map(map) => map
+                .get()
+                .entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), T::from_value(value.clone())?)))
+                .collect(),
+            _ => Err(type_mismatch("map", &value)),
+        }
+    }
+}
+
+/// Tuples become lists of the same length.
+macro_rules! tuple_conversions {
+    ($len:literal; $($name:ident),+) => {
+        impl<$($name: IntoValue),+> IntoValue for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_value(self, vm: &mut Vm) -> Res<Value> {
+                let ($($name,)+) = self;
+                let items = vec![$($name.into_value(vm)?),+];
+                vm.new_list(items)
+            }
+        }
+
+        impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+            fn from_value(value: Value) -> Res<Self> {
+                let expected = concat!("list of ", $len);
+                let Value::List(list) = value else {
+                    return Err(type_mismatch(expected, &value));
+                };
+
+                let items = &list.get().items;
+                if items.len() != $len {
+                    return Err(RxError::new(Runtime::new(
+                        ErrorKind::TypeError,
+                        &format!("Expected {expected} but got list of {}.", items.len()),
+                    )));
+                }
+
+                let mut items = items.iter().cloned();
+                Ok(($($name::from_value(items.next().unwrap_or(Value::Nil))?,)+))
+            }
+        }
+    };
+}
+
+tuple_conversions!(1; A);
+tuple_conversions!(2; A, B);
+tuple_conversions!(3; A, B, C);
+tuple_conversions!(4; A, B, C, D);
+tuple_conversions!(5; A, B, C, D, E);
+tuple_conversions!(6; A, B, C, D, E, F);
+
+macro_rules! native_signature {
+    ($arity:literal; $($arg:ident),*) => {
+        impl<Func, Ret, $($arg),*> NativeSignature<($($arg,)*)> for Func
+        where
+            Func: Fn($($arg),*) -> Ret,
+            Ret: IntoValue,
+            $($arg: FromValue,)*
+        {
+            fn arity() -> u8 {
+                $arity
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn invoke(&self, vm: &mut Vm, args: &[Value]) -> Res<Value> {
+                let mut args = args.iter().cloned();
+                let result = self($($arg::from_value(args.next().unwrap_or(Value::Nil))?),*);
+                result.into_value(vm)
+            }
+        }
+    };
+}
+
+native_signature!(0;);
+native_signature!(1; A);
+native_signature!(2; A, B);
+native_signature!(3; A, B, C);
+native_signature!(4; A, B, C, D);
+native_signature!(5; A, B, C, D, E);
+native_signature!(6; A, B, C, D, E, F);
@@ -1,9 +1,12 @@
-use std::{fmt, io};
+use std::{error, fmt, io};
+
+use crate::compiler::Span;
 
 #[derive(Debug)]
 pub enum RxError {
     Compile(Compile),
     Runtime(Runtime),
+    Io(io::Error),
 }
 
 impl RxError {
@@ -13,32 +16,174 @@ impl RxError {
     {
         err.into()
     }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Compile(e) => e.kind,
+            Self::Runtime(e) => e.kind,
+            Self::Io(_) => ErrorKind::Io,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind().code()
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Compile(e) => e.span,
+            _ => None,
+        }
+    }
+}
+
+/// Category of an error.
+///
+/// Every kind maps to a stable code, so hosts can match on errors without
+/// parsing their messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Io,
+    SyntaxError,
+    TypeError,
+    NameError,
+    ArityError,
+    ArithmeticError,
+    HostError,
+    StackOverflow,
+    StackUnderflow,
+    BudgetExceeded,
+    Interrupted,
+    OutOfMemory,
+    InvalidBytecode,
+}
+
+impl ErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io => "E0001",
+            Self::SyntaxError => "E0100",
+            Self::TypeError => "E0200",
+            Self::NameError => "E0201",
+            Self::ArityError => "E0202",
+            Self::ArithmeticError => "E0203",
+            Self::HostError => "E0204",
+            Self::StackOverflow => "E0300",
+            Self::StackUnderflow => "E0301",
+            Self::BudgetExceeded => "E0302",
+            Self::Interrupted => "E0303",
+            Self::OutOfMemory => "E0304",
+            Self::InvalidBytecode => "E0305",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Io => "IoError",
+            Self::SyntaxError => "SyntaxError",
+            Self::TypeError => "TypeError",
+            Self::NameError => "NameError",
+            Self::ArityError => "ArityError",
+            Self::ArithmeticError => "ArithmeticError",
+            Self::HostError => "HostError",
+            Self::StackOverflow => "StackOverflow",
+            Self::StackUnderflow => "StackUnderflow",
+            Self::BudgetExceeded => "BudgetExceeded",
+            Self::Interrupted => "Interrupted",
+            Self::OutOfMemory => "OutOfMemory",
+            Self::InvalidBytecode => "InvalidBytecode",
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Compile {
+    kind: ErrorKind,
     msg: String,
+    span: Option<Span>,
+    lexeme: Option<String>,
 }
 
 impl Compile {
-    pub fn new(msg: &str) -> Self {
+    pub fn new(kind: ErrorKind, msg: &str) -> Self {
         Self {
+            kind,
             msg: msg.to_owned(),
+            span: None,
+            lexeme: None,
         }
     }
+
+    /// Points the error at the offending token.
+    pub fn at(mut self, span: Span, lexeme: &str) -> Self {
+        self.span = Some(span);
+        self.lexeme = Some(lexeme.to_owned());
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
 }
 
 #[derive(Debug)]
 pub struct Runtime {
+    kind: ErrorKind,
     msg: String,
+    trace: Vec<Frame>,
 }
 
 impl Runtime {
-    pub fn new(msg: &str) -> Self {
+    pub fn new(kind: ErrorKind, msg: &str) -> Self {
         Self {
+            kind,
             msg: msg.to_owned(),
+            trace: Vec::new(),
         }
     }
+
+    /// `trace` lists the active frames innermost first.
+    pub fn with_trace(mut self, trace: Vec<Frame>) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn trace(&self) -> &[Frame] {
+        &self.trace
+    }
+
+    /// Line of the innermost frame that has one.
+    pub fn line(&self) -> usize {
+        self.trace
+            .iter()
+            .find_map(|frame| frame.line)
+            .unwrap_or_default()
+    }
+}
+
+/// One entry of a runtime stack trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub name: String,
+    pub file: String,
+    /// `None` for host-native frames, which have no roxy source line.
+    pub line: Option<usize>,
 }
 
 impl From<Compile> for RxError {
@@ -53,31 +198,72 @@ impl From<Runtime> for RxError {
     }
 }
 
+impl From<io::Error> for RxError {
+    fn from(value: io::Error) -> Self {
+        RxError::Io(value)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 impl fmt::Display for Compile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.msg)
+        if let Some(span) = self.span {
+            write!(f, "[line {}] ", span.2)?;
+        }
+        write!(f, "{}", self.kind)?;
+        match self.lexeme.as_deref() {
+            Some("") | None => (),
+            Some(lexeme) => write!(f, " at {lexeme}")?,
+        }
+        write!(f, ": {}", self.msg)
     }
 }
 
 impl fmt::Display for Runtime {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.msg)
+        write!(f, "{}: {}", self.kind, self.msg)?;
+        for frame in &self.trace {
+            write!(f, "\n{frame}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "[line {}] in {} ({})", line, self.name, self.file),
+            None => write!(f, "[native] in {}", self.name),
+        }
     }
 }
 
 impl fmt::Display for RxError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Compile(e) => write!(f, "compile error: {}", e),
-            Self::Runtime(e) => write!(f, "runtime error: {}", e),
+            Self::Compile(e) => write!(f, "compile error[{}]: {}", e.kind.code(), e),
+            Self::Runtime(e) => write!(f, "runtime error[{}]: {}", e.kind.code(), e),
+            Self::Io(e) => write!(f, "io error[{}]: {}", ErrorKind::Io.code(), e),
         }
     }
 }
 
-impl From<io::Error> for RxError {
-    fn from(value: io::Error) -> Self {
-        RxError::Compile(Compile {
-            msg: value.to_string(),
-        })
+impl error::Error for Compile {}
+
+impl error::Error for Runtime {}
+
+impl error::Error for RxError {
+    /// Only an I/O error has a source; compile and runtime errors already
+    /// print their message, so reporting it again as a source would repeat it.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Compile(_) | Self::Runtime(_) => None,
+            Self::Io(e) => Some(e),
+        }
     }
 }
@@ -0,0 +1,316 @@
+//! C ABI for embedding roxy.
+//!
+//! `build.rs` generates `include/roxy.h` from the items below, so their
+//! shapes are limited to what it understands. Handles are opaque to C: a `RoxyVm` is only ever touched
+//! through the functions below and released with `roxy_vm_free`.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::{
+    error::{ErrorKind, Runtime, RxError},
+    value::Value,
+    vm::Vm,
+    Res,
+};
+
+pub const ROXY_OK: c_int = 0;
+pub const ROXY_ERROR: c_int = -1;
+
+/// A VM together with the last result and error it reported.
+pub struct RoxyVm {
+    vm: Vm,
+    /// Keeps the string a `RoxyValue` result points into alive.
+    result: Option<Value>,
+    error: Option<CString>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoxyType {
+    Nil = 0,
+    Bool = 1,
+    Int = 2,
+    Float = 3,
+    String = 4,
+    /// Functions and host objects, which C can not look into.
+    Object = 5,
+}
+
+/// A value crossing the C boundary.
+///
+/// Only the field matching `ty` is meaningful. `string` is not NUL
+/// terminated; it points into the VM heap and stays valid until the next
+/// call on the VM, or for the duration of a native call for its arguments.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RoxyValue {
+    pub ty: RoxyType,
+    pub boolean: bool,
+    pub integer: i64,
+    pub number: f64,
+    pub string: *const c_char,
+    pub length: usize,
+}
+
+/// Native function implemented in C.
+///
+/// Returns `ROXY_OK` and stores the result in `out`, or anything else to
+/// raise a runtime error, optionally with a string `out` as its message.
+pub type RoxyNativeFn = extern "C" fn(
+    userdata: *mut c_void,
+    args: *const RoxyValue,
+    argc: usize,
+    out: *mut RoxyValue,
+) -> c_int;
+
+/// `userdata` pointer handed to a C native.
+///
+/// Natives travel with their VM, so the pointer has to be `Send` and `Sync`;
+/// making sure the pointee may be used from the thread running the VM is up
+/// to the C caller, as `roxy_register_native` documents.
+#[derive(Clone, Copy)]
+struct UserPtr(*mut c_void);
+
+unsafe impl Send for UserPtr {}
+unsafe impl Sync for UserPtr {}
+
+impl UserPtr {
+    // a method, so closures capture the wrapper and not the bare pointer
+    fn get(self) -> *mut c_void {
+        self.0
+    }
+}
+
+impl RoxyValue {
+    const NIL: Self = Self {
+        ty: RoxyType::Nil,
+        boolean: false,
+        integer: 0,
+        number: 0.0,
+        string: ptr::null(),
+        length: 0,
+    };
+
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::Nil => Self::NIL,
+            Value::Bool(boolean) => Self {
+                ty: RoxyType::Bool,
+                boolean,
+                ..Self::NIL
+            },
+            Value::Int(integer) => Self {
+                ty: RoxyType::Int,
+                integer,
+                ..Self::NIL
+            },
+            Value::Float(number) => Self {
+                ty: RoxyType::Float,
+                number,
+                ..Self::NIL
+            },
+            Value::String(string) => Self {
+                ty: RoxyType::String,
+                string: string.get().as_ptr().cast(),
+                length: string.get().len(),
+                ..Self::NIL
+            },
+            _ => Self {
+                ty: RoxyType::Object,
+                ..Self::NIL
+            },
+        }
+    }
+
+    /// # Safety
+    ///
+    /// A string value must point at `length` readable bytes.
+    unsafe fn to_value(self, vm: &mut Vm) -> Res<Value> {
+        match self.ty {
+            RoxyType::Nil => Ok(Value::Nil),
+            RoxyType::Bool => Ok(Value::Bool(self.boolean)),
+            RoxyType::Int => Ok(Value::Int(self.integer)),
+            RoxyType::Float => Ok(Value::Float(self.number)),
+            RoxyType::String => vm.new_string(&self.as_string()),
+            RoxyType::Object => Err(RxError::new(Runtime::new(
+                ErrorKind::TypeError,
+                "Objects can not be passed in from C.",
+            ))),
+        }
+    }
+
+    unsafe fn as_string(&self) -> String {
+        if self.string.is_null() {
+            return String::new();
+        }
+        let bytes = slice::from_raw_parts(self.string.cast::<u8>(), self.length);
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+impl RoxyVm {
+    fn record<T>(&mut self, result: Res<T>) -> Option<T> {
+        match result {
+            Ok(value) => {
+                self.error = None;
+                Some(value)
+            }
+            Err(e) => {
+                self.set_error(&e.to_string());
+                None
+            }
+        }
+    }
+
+    fn set_error(&mut self, msg: &str) {
+        // interior NULs would truncate the message on the C side anyway
+        let msg = msg.replace('\0', " ");
+        self.error = CString::new(msg).ok();
+    }
+}
+
+/// Creates a VM with the default limits. Never returns NULL.
+#[no_mangle]
+pub extern "C" fn roxy_vm_new() -> *mut RoxyVm {
+    Box::into_raw(Box::new(RoxyVm {
+        vm: Vm::new(),
+        result: None,
+        error: None,
+    }))
+}
+
+/// Frees a VM created by `roxy_vm_new`. NULL is ignored.
+///
+/// # Safety
+///
+/// `vm` must come from `roxy_vm_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn roxy_vm_free(vm: *mut RoxyVm) {
+    if !vm.is_null() {
+        drop(Box::from_raw(vm));
+    }
+}
+
+/// Runs the NUL terminated `source`, storing its result in `out` unless
+/// `out` is NULL.
+///
+/// # Safety
+///
+/// `vm` must be a live handle and `source` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn roxy_eval(
+    vm: *mut RoxyVm,
+    source: *const c_char,
+    out: *mut RoxyValue,
+) -> c_int {
+    let Some(handle) = vm.as_mut() else {
+        return ROXY_ERROR;
+    };
+    if source.is_null() {
+        handle.set_error("source is NULL");
+        return ROXY_ERROR;
+    }
+
+    let source = CStr::from_ptr(source).to_string_lossy();
+    handle.result = None;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| handle.vm.eval(&source)));
+    let result = match result {
+        Ok(result) => handle.record(result),
+        Err(_) => {
+            handle.set_error("roxy panicked");
+            None
+        }
+    };
+
+    match result {
+        Some(value) => {
+            if let Some(out) = out.as_mut() {
+                *out = RoxyValue::from_value(value.clone());
+            }
+            handle.result = Some(value);
+            ROXY_OK
+        }
+        None => ROXY_ERROR,
+    }
+}
+
+/// Last error reported by `vm`, or NULL if the last call succeeded.
+///
+/// The string belongs to the VM and is valid until its next call.
+///
+/// # Safety
+///
+/// `vm` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn roxy_get_error(vm: *const RoxyVm) -> *const c_char {
+    match vm.as_ref().and_then(|vm| vm.error.as_ref()) {
+        Some(error) => error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Registers `function` as the global native `name`.
+///
+/// A negative `arity` accepts any number of arguments. `userdata` is handed
+/// back to every call.
+///
+/// # Safety
+///
+/// `vm` must be a live handle, `name` a valid C string, and `userdata` must
+/// stay valid for as long as the VM lives, on whichever thread runs it.
+#[no_mangle]
+pub unsafe extern "C" fn roxy_register_native(
+    vm: *mut RoxyVm,
+    name: *const c_char,
+    arity: c_int,
+    function: RoxyNativeFn,
+    userdata: *mut c_void,
+) -> c_int {
+    let Some(handle) = vm.as_mut() else {
+        return ROXY_ERROR;
+    };
+    if name.is_null() {
+        handle.set_error("name is NULL");
+        return ROXY_ERROR;
+    }
+
+    let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+    let arity = match u8::try_from(arity) {
+        Ok(arity) => Some(arity),
+        Err(_) if arity < 0 => None,
+        Err(_) => {
+            handle.set_error(&format!("arity {arity} is larger than 255"));
+            return ROXY_ERROR;
+        }
+    };
+
+    let userdata = UserPtr(userdata);
+    let native = move |vm: &mut Vm, args: &[Value]| {
+        let args = args
+            .iter()
+            .map(|value| RoxyValue::from_value(value.clone()))
+            .collect::<Vec<_>>();
+        let mut out = RoxyValue::NIL;
+
+        let status = function(userdata.get(), args.as_ptr(), args.len(), &mut out);
+        if status == ROXY_OK {
+            return out.to_value(vm);
+        }
+
+        let msg = match out.ty {
+            RoxyType::String => out.as_string(),
+            _ => format!("native function failed with status {status}"),
+        };
+        Err(RxError::new(Runtime::new(ErrorKind::HostError, &msg)))
+    };
+
+    let result = handle.vm.register_native(&name, arity, native);
+    match handle.record(result) {
+        Some(()) => ROXY_OK,
+        None => ROXY_ERROR,
+    }
+}
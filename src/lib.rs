@@ -1,7 +1,10 @@
 pub mod chunks;
 pub mod compiler;
+pub mod convert;
 pub mod error;
+pub mod ffi;
 pub mod object;
+pub mod userdata;
 pub mod value;
 pub mod vm;
 
@@ -1,19 +1,558 @@
-use std::env;
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process,
+};
 
-use roxy::{chunks::Chunk, vm::Vm};
+use roxy::{
+    chunks::{assemble, Disassembler, Opcode},
+    compiler::{self, tokenize, Parser, TokenType},
+    error::RxError,
+    object::{Function, Heap, ObjRef},
+    value::Value,
+    vm::{SharedBuffer, TraceFormat, Tracer, Vm},
+    Res,
+};
+
+const USAGE: &str = "\
+Usage: roxy [command] [options] [script | -] [args...]
+
+Commands:
+    run <script | -> [args...]  Run a script, the default with a script
+    repl                        Start an interactive session, the default without one
+    check <files...>            Compile without running and report errors
+    compile <file> [-o <out>]   Write the bytecode listing of a script as .rxasm
+    dis <file>                  Print the bytecode listing of a .rx or .rxasm file
+    fmt [--check] <files...>    Reformat scripts in place, `-` prints to stdout
+    test [paths...]             Run scripts and compare output to `// expect:` comments
+
+Options:
+    -e <source>                 Run <source> instead of a script and print its value
+    -o <out>                    Output file of `compile`, `-` for stdout
+    --emit=tokens|ast|bytecode  Print a compiler stage instead of running, with compile
+                                or without a command
+    --check                     Only list the files `fmt` would change
+    --trace                     Trace executed instructions to stderr, or set ROXY_TRACE=1
+    --trace-file=<path>         Write the trace to a file (ROXY_TRACE_FILE)
+    --trace-format=text|json    Trace as text or JSON lines (ROXY_TRACE_FORMAT)
+    --trace-lines=<1,4-9>       Only trace these source lines (ROXY_TRACE_LINES)
+    --trace-ops=<Add,Call>      Only trace these opcodes (ROXY_TRACE_OPS)
+    -h, --help                  Print this text
+    -V, --version               Print the version
+
+Arguments after the script are available to it as the list `args`.
+";
+
+// exit codes, following sysexits.h
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
 
 fn main() {
-    let mut args = env::args();
+    let code = match Cli::parse(env::args().skip(1)) {
+        Ok(cli) => cli.run(),
+        Err(msg) => {
+            eprintln!("roxy: {msg}");
+            eprint!("{USAGE}");
+            EX_USAGE
+        }
+    };
+    process::exit(code);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Run,
+    Repl,
+    Check,
+    Compile,
+    Dis,
+    Fmt,
+    Test,
+    Help,
+    Version,
+}
+
+/// Compiler stage printed by `--emit` instead of running the script.
+#[derive(Clone, Copy)]
+enum Emit {
+    Tokens,
+    Ast,
+    Bytecode,
+}
+
+struct Cli {
+    command: Command,
+    eval: Option<String>,
+    emit: Option<Emit>,
+    output: Option<String>,
+    check: bool,
+    trace: TraceArgs,
+    inputs: Vec<String>,
+    /// Arguments after the script, passed on to it.
+    script_args: Vec<String>,
+}
+
+impl Cli {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.peekable();
+
+        let explicit = match args.peek().map(String::as_str) {
+            Some("run") => Some(Command::Run),
+            Some("repl") => Some(Command::Repl),
+            Some("check") => Some(Command::Check),
+            Some("compile") => Some(Command::Compile),
+            Some("dis") => Some(Command::Dis),
+            Some("fmt") => Some(Command::Fmt),
+            Some("test") => Some(Command::Test),
+            Some("help") => Some(Command::Help),
+            _ => None,
+        };
+        if explicit.is_some() {
+            args.next();
+        }
+
+        let mut cli = Cli {
+            command: explicit.unwrap_or(Command::Run),
+            eval: None,
+            emit: None,
+            output: None,
+            check: false,
+            trace: TraceArgs::from_env()?,
+            inputs: Vec::new(),
+            script_args: Vec::new(),
+        };
+
+        let mut no_options = false;
+        while let Some(arg) = args.next() {
+            // everything after the script belongs to the script
+            let forwarding =
+                cli.command == Command::Run && (cli.eval.is_some() || !cli.inputs.is_empty());
+            if forwarding {
+                cli.script_args.push(arg);
+                continue;
+            }
+
+            if no_options || arg == "-" || !arg.starts_with('-') {
+                cli.inputs.push(arg);
+                continue;
+            }
+
+            match arg.as_str() {
+                "--" => no_options = true,
+                "-h" | "--help" => cli.command = Command::Help,
+                "-V" | "--version" => cli.command = Command::Version,
+                "-e" => cli.eval = Some(args.next().ok_or("-e needs a source argument.")?),
+                "-o" => cli.output = Some(args.next().ok_or("-o needs a file argument.")?),
+                "--check" => cli.check = true,
+                "--trace" => cli.trace.enabled = true,
+                _ => {
+                    if let Some(mode) = arg.strip_prefix("--emit=") {
+                        cli.emit = Some(match mode {
+                            "tokens" => Emit::Tokens,
+                            "ast" => Emit::Ast,
+                            "bytecode" => Emit::Bytecode,
+                            _ => return Err(format!("Unknown emit mode '{mode}'.")),
+                        });
+                    } else if let Some(option) = arg.strip_prefix("--trace-") {
+                        let (key, value) = option.split_once('=').unwrap_or((option, ""));
+                        cli.trace.set(key, value)?;
+                    } else {
+                        return Err(format!("Unknown option '{arg}'."));
+                    }
+                }
+            }
+        }
+
+        // `roxy` alone starts the REPL, `roxy --emit=ast file` compiles
+        if explicit.is_none() && cli.command == Command::Run {
+            if cli.emit.is_some() {
+                cli.command = Command::Compile;
+                cli.output.get_or_insert_with(|| "-".to_owned());
+            } else if cli.eval.is_none() && cli.inputs.is_empty() {
+                cli.command = Command::Repl;
+            }
+        }
+        cli.validate()?;
+        Ok(cli)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let inputs = self.inputs.len();
+        match self.command {
+            Command::Run
+            | Command::Repl
+            | Command::Check
+            | Command::Dis
+            | Command::Fmt
+            | Command::Test
+                if self.emit.is_some() =>
+            {
+                Err("--emit only works with compile.".to_owned())
+            }
+            Command::Run if self.eval.is_none() && inputs == 0 => {
+                Err("run needs a script, `-` or -e.".to_owned())
+            }
+            Command::Repl if inputs > 0 => Err("repl takes no files.".to_owned()),
+            Command::Check | Command::Fmt if inputs == 0 => {
+                Err("Expect at least one file.".to_owned())
+            }
+            Command::Compile | Command::Dis if inputs != 1 => {
+                Err("Expect exactly one file.".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn run(self) -> i32 {
+        match self.command {
+            Command::Help => {
+                print!("{USAGE}");
+                0
+            }
+            Command::Version => {
+                println!("roxy {}", env!("CARGO_PKG_VERSION"));
+                0
+            }
+            Command::Run => self.run_script(),
+            Command::Repl => report(self.vm().and_then(|mut vm| vm.run_repl())),
+            Command::Check => self.check(),
+            Command::Compile => report(self.compile()),
+            Command::Dis => report(self.dis()),
+            Command::Fmt => self.fmt(),
+            Command::Test => self.test(),
+        }
+    }
+
+    fn vm(&self) -> Res<Vm> {
+        let mut vm = Vm::new();
+        vm.set_tracer(self.trace.tracer()?);
+        Ok(vm)
+    }
+
+    fn run_script(&self) -> i32 {
+        let result = self.vm().and_then(|mut vm| {
+            let args = self
+                .script_args
+                .iter()
+                .map(|arg| vm.new_string(arg))
+                .collect::<Res<Vec<_>>>()?;
+            let args = vm.new_list(args)?;
+            vm.set_global("args", args);
+
+            if let Some(source) = &self.eval {
+                match vm.eval_source("<eval>", source)? {
+                    Value::Nil => (),
+                    value => println!("{value}"),
+                }
+                return Ok(());
+            }
+
+            let (name, source) = read_input(&self.inputs[0])?;
+            if name.ends_with(".rxasm") {
+                vm.eval_asm(&name, &source)?;
+            } else {
+                vm.eval_source(&name, &source)?;
+            }
+            Ok(())
+        });
+        report(result)
+    }
+
+    fn check(&self) -> i32 {
+        let mut code = 0;
+        for input in &self.inputs {
+            let result = read_input(input).and_then(|(name, source)| {
+                build(&name, &source, &mut Heap::new(None, None)).map(|_| ())
+            });
+            if let Err(e) = result {
+                eprintln!("{input}: {e}");
+                code = code.max(exit_code(&e));
+            }
+        }
+        code
+    }
+
+    fn compile(&self) -> Res<()> {
+        let input = &self.inputs[0];
+        let (name, source) = read_input(input)?;
+
+        let emit = self.emit.unwrap_or(Emit::Bytecode);
+        let output = match (&self.output, emit) {
+            (Some(output), _) => output.clone(),
+            (None, Emit::Bytecode) if input != "-" => Path::new(input)
+                .with_extension("rxasm")
+                .display()
+                .to_string(),
+            (None, _) => "-".to_owned(),
+        };
+
+        let mut out: Box<dyn Write> = match output.as_str() {
+            "-" => Box::new(io::stdout().lock()),
+            path => Box::new(BufWriter::new(File::create(path)?)),
+        };
+        match emit {
+            Emit::Tokens => {
+                for token in tokenize(&source) {
+                    if !matches!(token.kind, TokenType::Whitespace(_)) {
+                        writeln!(out, "{:>4} {:?}", token.line(), token.kind)?;
+                    }
+                }
+            }
+            Emit::Ast => write!(out, "{}", Parser::new(&source).parse()?)?,
+            Emit::Bytecode => {
+                let script = build(&name, &source, &mut Heap::new(None, None))?;
+                Disassembler::new(&script.get().chunk, None).disassemble(&mut out, "script")?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn dis(&self) -> Res<()> {
+        let (name, source) = read_input(&self.inputs[0])?;
+        let script = build(&name, &source, &mut Heap::new(None, None))?;
+        Disassembler::new(&script.get().chunk, None).disassemble(&mut io::stdout(), "script")?;
+        Ok(())
+    }
+
+    fn fmt(&self) -> i32 {
+        let mut code = 0;
+        for input in &self.inputs {
+            let result = read_input(input).and_then(|(_, source)| {
+                let formatted = compiler::format(&source)?;
+                if input == "-" {
+                    print!("{formatted}");
+                } else if formatted != source {
+                    if self.check {
+                        println!("{input}");
+                        code = code.max(1);
+                    } else {
+                        fs::write(input, formatted)?;
+                    }
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                eprintln!("{input}: {e}");
+                code = code.max(exit_code(&e));
+            }
+        }
+        code
+    }
+
+    fn test(&self) -> i32 {
+        let roots = match self.inputs.as_slice() {
+            [] => vec![PathBuf::from(".")],
+            inputs => inputs.iter().map(PathBuf::from).collect(),
+        };
+
+        let mut files = Vec::new();
+        for root in roots {
+            if let Err(e) = collect_scripts(&root, &mut files) {
+                eprintln!("{}: {e}", root.display());
+                return EX_IOERR;
+            }
+        }
+        files.sort();
+
+        let mut failed = 0;
+        for file in &files {
+            match run_test(file) {
+                Ok(()) => println!("PASS {}", file.display()),
+                Err(reason) => {
+                    failed += 1;
+                    println!("FAIL {}\n    {reason}", file.display());
+                }
+            }
+        }
+
+        println!("\n{} passed, {failed} failed", files.len() - failed);
+        if failed > 0 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// Tracing options, from `ROXY_TRACE*` variables and `--trace*` flags.
+#[derive(Default)]
+struct TraceArgs {
+    enabled: bool,
+    file: Option<String>,
+    format: TraceFormat,
+    lines: Vec<RangeInclusive<usize>>,
+    opcodes: Vec<String>,
+}
+
+impl TraceArgs {
+    fn from_env() -> Result<Self, String> {
+        let mut args = Self::default();
+        for key in ["file", "format", "lines", "ops"] {
+            let var = format!("ROXY_TRACE_{}", key.to_uppercase());
+            if let Ok(value) = env::var(&var) {
+                args.set(key, &value).map_err(|e| format!("{var}: {e}"))?;
+            }
+        }
 
-    let program = args.next().unwrap();
+        // the variables above only configure tracing, ROXY_TRACE turns it on
+        args.enabled = env::var("ROXY_TRACE").is_ok_and(|v| !v.is_empty() && v != "0");
+        Ok(args)
+    }
+
+    /// Applies one `--trace-<key>=<value>` option, which turns tracing on.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "file" => self.file = Some(value.to_owned()),
+            "format" => {
+                self.format = match value {
+                    "text" => TraceFormat::Text,
+                    "json" => TraceFormat::Json,
+                    _ => return Err(format!("Unknown trace format '{value}'.")),
+                }
+            }
+            "lines" => {
+                for range in value.split(',') {
+                    let (start, end) = range.split_once('-').unwrap_or((range, range));
+                    match (start.trim().parse(), end.trim().parse()) {
+                        (Ok(start), Ok(end)) => self.lines.push(start..=end),
+                        _ => return Err(format!("Invalid line range '{range}'.")),
+                    }
+                }
+            }
+            "ops" => {
+                for op in value.split(',').map(str::trim) {
+                    let name = op.strip_prefix("OP_").unwrap_or(op);
+                    if !Opcode::NAMES
+                        .iter()
+                        .any(|known| known[3..].eq_ignore_ascii_case(name))
+                    {
+                        return Err(format!("Unknown opcode '{op}'."));
+                    }
+                    self.opcodes.push(op.to_owned());
+                }
+            }
+            _ => return Err(format!("Unknown option '--trace-{key}'.")),
+        }
+        self.enabled = true;
+        Ok(())
+    }
+
+    fn tracer(&self) -> io::Result<Option<Tracer>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+
+        let tracer = match &self.file {
+            Some(path) => Tracer::new(BufWriter::new(File::create(path)?)),
+            None => Tracer::new(io::stderr()),
+        };
+        let tracer = tracer
+            .format(self.format)
+            .lines(self.lines.iter().cloned())
+            .opcodes(self.opcodes.iter().cloned());
+        Ok(Some(tracer))
+    }
+}
+
+/// Reads `input`, `-` being stdin, returning the name to report it by.
+fn read_input(input: &str) -> Res<(String, String)> {
+    if input == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        return Ok(("<stdin>".to_owned(), source));
+    }
+    Ok((input.to_owned(), fs::read_to_string(input)?))
+}
 
-    let mut chunk = Chunk::new();
-    let mut vm = Vm::new(&mut chunk);
+/// Compiles a script, or assembles it if it is a `.rxasm` file.
+fn build(name: &str, source: &str, heap: &mut Heap) -> Res<ObjRef<Function>> {
+    if name.ends_with(".rxasm") {
+        let script = assemble(source, name, heap)?;
+        return heap.alloc_function(script);
+    }
+    compiler::compile(source, name, heap)
+}
+
+fn report(result: Res<()>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            exit_code(&e)
+        }
+    }
+}
+
+fn exit_code(e: &RxError) -> i32 {
+    match e {
+        RxError::Compile(_) => EX_DATAERR,
+        RxError::Runtime(_) => EX_SOFTWARE,
+        RxError::Io(_) => EX_IOERR,
+    }
+}
+
+/// Adds every `.rx` file under `path` to `files`, skipping hidden
+/// directories and build output.
+fn collect_scripts(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_scripts(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rx") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Runs a test script, checking each printed line against the next
+/// `// expect: <line>` comment and a failure against `// expect error: <text>`.
+fn run_test(file: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(file).map_err(|e| e.to_string())?;
+
+    let mut expected = Vec::new();
+    let mut expected_error = None;
+    for token in tokenize(&source) {
+        if let TokenType::Comment(text) = token.kind {
+            if let Some(line) = text.strip_prefix("// expect: ") {
+                expected.push(line.to_owned());
+            } else if let Some(error) = text.strip_prefix("// expect error: ") {
+                expected_error = Some(error.to_owned());
+            }
+        }
+    }
+
+    let out = SharedBuffer::new();
+    let mut vm = Vm::builder().stdout(out.clone()).stderr(io::sink()).build();
+    let result = vm.eval_source(&file.display().to_string(), &source);
+
+    let output = out.contents();
+    let actual = output.lines().collect::<Vec<_>>();
+    if actual != expected {
+        return Err(format!("expected output {expected:?}, got {actual:?}"));
+    }
 
-    match args.len() {
-        0 => vm.run_repl(),
-        1 => vm.run_file(&args.next().unwrap()),
-        _ => panic!("Usage: {program} [script]"),
+    match (result, expected_error) {
+        (Ok(_), None) => Ok(()),
+        (Ok(_), Some(error)) => Err(format!("expected error '{error}', but it ran")),
+        (Err(e), Some(error)) if e.to_string().contains(&error) => Ok(()),
+        (Err(e), _) => Err(format!("unexpected error: {e}")),
     }
-    .unwrap();
 }
@@ -1,27 +1,340 @@
-use std::fmt::Display;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Write},
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-#[derive(Debug)]
-pub struct ObjRef<T: Display> {
-    pub value: *mut T,
+use crate::{
+    chunks::{Chunk, Opcode},
+    error::{ErrorKind, Runtime, RxError},
+    userdata::HostObject,
+    value::Value,
+    vm::Vm,
+    Res,
+};
+
+/// Shared handle to a heap object.
+///
+/// Every handle keeps the object alive on its own, so a handle never
+/// dangles, even after its VM is dropped. Dropping the last handle frees the
+/// object and returns its size to the `Heap` that allocated it.
+pub struct ObjRef<T: Display>(Arc<Obj<T>>);
+
+struct Obj<T> {
+    value: T,
+    /// Bytes accounted for the object, returned to `usage` when it is freed.
+    size: usize,
+    usage: Option<Arc<Usage>>,
+}
+
+impl<T> Drop for Obj<T> {
+    fn drop(&mut self) {
+        if let Some(usage) = &self.usage {
+            usage.bytes.fetch_sub(self.size, Ordering::Relaxed);
+            usage.objects.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
 }
 
 impl<T: Display> ObjRef<T> {
-    pub fn new(value: *mut T) -> ObjRef<T> {
-        Self { value }
+    /// Wraps `value` without accounting it on any heap.
+    pub fn new(value: T) -> ObjRef<T> {
+        Self(Arc::new(Obj {
+            value,
+            size: 0,
+            usage: None,
+        }))
+    }
+
+    pub fn get(&self) -> &T {
+        &self.0.value
+    }
+
+    /// Whether both handles point at the same object.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
     }
 }
 
-impl<T: Display> Copy for ObjRef<T> {}
+impl<T: Display + fmt::Debug> fmt::Debug for ObjRef<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ObjRef").field(&self.0.value).finish()
+    }
+}
 
 impl<T: Display> Clone for ObjRef<T> {
     fn clone(&self) -> Self {
-        *self
+        Self(Arc::clone(&self.0))
     }
 }
 
 impl<T: Display> Display for ObjRef<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let x = unsafe { &*self.value };
-        write!(f, "{x}",)
+        write!(f, "{}", self.0.value)
     }
 }
+
+/// A compiled roxy function, or the top level code of a script.
+#[derive(Debug)]
+pub struct Function {
+    pub name: Arc<str>,
+    pub file: Arc<str>,
+    pub arity: u8,
+    pub chunk: Chunk,
+}
+
+impl Function {
+    pub fn new(name: Arc<str>, file: Arc<str>) -> Self {
+        Self {
+            name,
+            file,
+            arity: 0,
+            chunk: Chunk::new(),
+        }
+    }
+
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.chunk.code.capacity() * mem::size_of::<Opcode>()
+            + self.chunk.constants.capacity() * mem::size_of::<Value>()
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.name)
+    }
+}
+
+pub type NativeFn = dyn Fn(&mut Vm, &[Value]) -> Res<Value> + Send + Sync;
+
+/// A host function callable from roxy.
+pub struct Native {
+    pub name: Arc<str>,
+    /// Expected argument count, any count is accepted when `None`.
+    pub arity: Option<u8>,
+    pub function: Box<NativeFn>,
+}
+
+impl fmt::Debug for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Native")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Native {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// A list of values, created by the host.
+#[derive(Debug, Default)]
+pub struct List {
+    pub items: Vec<Value>,
+}
+
+impl List {
+    fn size(&self) -> usize {
+        mem::size_of::<Self>() + self.items.capacity() * mem::size_of::<Value>()
+    }
+}
+
+impl Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// A map from strings to values, created by the host.
+#[derive(Debug, Default)]
+pub struct Map {
+    pub entries: HashMap<String, Value>,
+}
+
+impl Map {
+    fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .entries
+                .keys()
+                .map(|key| key.capacity() + mem::size_of::<(String, Value)>())
+                .sum::<usize>()
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // sorted so the output does not depend on hash order
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| *key);
+
+        write!(f, "{{")?;
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{key}: {value}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Allocation counters of a `Heap`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Bytes currently owned by live objects.
+    pub bytes: usize,
+    /// Highest value `bytes` has reached.
+    pub peak_bytes: usize,
+    /// Number of live objects.
+    pub objects: usize,
+    /// Number of allocations made over the heap's lifetime.
+    pub allocations: usize,
+}
+
+/// Size of the live objects of a heap, shared with the objects so they can
+/// give their size back when freed.
+#[derive(Debug, Default)]
+struct Usage {
+    bytes: AtomicUsize,
+    objects: AtomicUsize,
+}
+
+/// Allocator of every object a VM creates.
+///
+/// Objects are reference counted and freed when their last `ObjRef` is
+/// dropped. Only live objects count against the configured limits; an
+/// allocation past them fails with `OutOfMemory`.
+#[derive(Debug, Default)]
+pub struct Heap {
+    usage: Arc<Usage>,
+    peak_bytes: usize,
+    allocations: usize,
+    max_bytes: Option<usize>,
+    max_string_len: Option<usize>,
+}
+
+impl Heap {
+    pub fn new(max_bytes: Option<usize>, max_string_len: Option<usize>) -> Self {
+        Self {
+            max_bytes,
+            max_string_len,
+            ..Self::default()
+        }
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            bytes: self.usage.bytes.load(Ordering::Relaxed),
+            peak_bytes: self.peak_bytes,
+            objects: self.usage.objects.load(Ordering::Relaxed),
+            allocations: self.allocations,
+        }
+    }
+
+    pub fn alloc_string(&mut self, string: String) -> Res<ObjRef<String>> {
+        self.check_string(string.len())?;
+        let size = mem::size_of::<String>() + string.capacity();
+        self.alloc(string, size)
+    }
+
+    pub fn alloc_function(&mut self, function: Function) -> Res<ObjRef<Function>> {
+        let size = function.size();
+        self.alloc(function, size)
+    }
+
+    pub fn alloc_native(&mut self, native: Native) -> Res<ObjRef<Native>> {
+        let size = mem::size_of::<Native>() + native.name.len();
+        self.alloc(native, size)
+    }
+
+    pub fn alloc_userdata(&mut self, object: HostObject) -> Res<ObjRef<HostObject>> {
+        let size = object.size();
+        self.alloc(object, size)
+    }
+
+    pub fn alloc_list(&mut self, list: List) -> Res<ObjRef<List>> {
+        let size = list.size();
+        self.alloc(list, size)
+    }
+
+    pub fn alloc_map(&mut self, map: Map) -> Res<ObjRef<Map>> {
+        let size = map.size();
+        self.alloc(map, size)
+    }
+
+    /// Concatenates two strings, checking the limits before allocating.
+    pub fn concat(&mut self, lhs: &str, rhs: &str) -> Res<ObjRef<String>> {
+        let len = lhs.len() + rhs.len();
+        self.check_string(len)?;
+
+        let mut string = String::with_capacity(len);
+        string.push_str(lhs);
+        string.push_str(rhs);
+        self.alloc_string(string)
+    }
+
+    /// Joins the printed forms of `parts`, failing as soon as the result
+    /// exceeds the string limit.
+    pub fn join(&mut self, parts: &[Value]) -> Res<ObjRef<String>> {
+        let mut joined = String::new();
+        for part in parts {
+            match part {
+                Value::String(s) => joined.push_str(s.get()),
+                other => write!(joined, "{other}").expect("writing to a String"),
+            }
+            self.check_string(joined.len())?;
+        }
+        self.alloc_string(joined)
+    }
+
+    fn check_string(&self, len: usize) -> Res<()> {
+        match self.max_string_len {
+            Some(max) if len > max => Err(out_of_memory(&format!(
+                "String of {len} bytes exceeds the maximum length of {max}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn alloc<T: Display>(&mut self, value: T, size: usize) -> Res<ObjRef<T>> {
+        let bytes = self.usage.bytes.load(Ordering::Relaxed) + size;
+        if let Some(max) = self.max_bytes {
+            if bytes > max {
+                return Err(out_of_memory(&format!(
+                    "Allocating {size} bytes exceeds the heap limit of {max}"
+                )));
+            }
+        }
+
+        self.usage.bytes.fetch_add(size, Ordering::Relaxed);
+        self.usage.objects.fetch_add(1, Ordering::Relaxed);
+        self.peak_bytes = self.peak_bytes.max(bytes);
+        self.allocations += 1;
+
+        Ok(ObjRef(Arc::new(Obj {
+            value,
+            size,
+            usage: Some(Arc::clone(&self.usage)),
+        })))
+    }
+}
+
+fn out_of_memory(msg: &str) -> RxError {
+    RxError::new(Runtime::new(ErrorKind::OutOfMemory, msg))
+}
@@ -0,0 +1,119 @@
+use std::{
+    fmt::{self, Display},
+    mem,
+    sync::{Mutex, MutexGuard, TryLockError},
+};
+
+use crate::{
+    error::{ErrorKind, Runtime, RxError},
+    value::Value,
+    vm::Vm,
+    Res,
+};
+
+/// A Rust value handed to scripts as an opaque object.
+///
+/// Scripts reach it through property access (`handle.name`), property
+/// assignment (`handle.name = value`) and method calls
+/// (`handle.lookup("key")`), which dispatch to the methods below. The
+/// defaults reject every access with a `NameError`.
+///
+/// Objects are `Send` so the VM holding them can move between threads.
+pub trait UserData: Display + Send {
+    fn type_name(&self) -> &str;
+
+    fn get(&self, vm: &mut Vm, name: &str) -> Res<Value> {
+        let _ = vm;
+        Err(no_member(self.type_name(), "property", name))
+    }
+
+    fn set(&mut self, vm: &mut Vm, name: &str, value: Value) -> Res<()> {
+        let _ = (vm, value);
+        Err(no_member(self.type_name(), "property", name))
+    }
+
+    fn call_method(&mut self, vm: &mut Vm, name: &str, args: &[Value]) -> Res<Value> {
+        let _ = (vm, args);
+        Err(no_member(self.type_name(), "method", name))
+    }
+}
+
+/// Heap object wrapping a `UserData` value.
+///
+/// The value sits behind a lock, so a method that calls back into the same
+/// object fails with a `TypeError` instead of aliasing it.
+/// Like every heap object, it is dropped once no value refers to it.
+pub struct HostObject {
+    type_name: String,
+    data: Mutex<Box<dyn UserData>>,
+    size: usize,
+}
+
+impl HostObject {
+    pub fn new<T: UserData + 'static>(data: T) -> Self {
+        let type_name = data.type_name().to_owned();
+        let size = mem::size_of::<Self>() + mem::size_of::<T>() + type_name.capacity();
+        Self {
+            type_name,
+            data: Mutex::new(Box::new(data)),
+            size,
+        }
+    }
+
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    pub fn get(&self, vm: &mut Vm, name: &str) -> Res<Value> {
+        self.lock()?.get(vm, name)
+    }
+
+    pub fn set(&self, vm: &mut Vm, name: &str, value: Value) -> Res<()> {
+        self.lock()?.set(vm, name, value)
+    }
+
+    pub fn call_method(&self, vm: &mut Vm, name: &str, args: &[Value]) -> Res<Value> {
+        self.lock()?.call_method(vm, name, args)
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    fn lock(&self) -> Res<MutexGuard<'_, Box<dyn UserData>>> {
+        match self.data.try_lock() {
+            Ok(guard) => Ok(guard),
+            // a panicking method can not leave the VM half updated, only the
+            // object, which stays usable like after any other error
+            Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(RxError::new(Runtime::new(
+                ErrorKind::TypeError,
+                &format!("{} is already in use.", self.type_name),
+            ))),
+        }
+    }
+}
+
+impl fmt::Debug for HostObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostObject")
+            .field("type", &self.type_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for HostObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.data.try_lock() {
+            Ok(data) => write!(f, "{data}"),
+            Err(_) => write!(f, "<{}>", self.type_name),
+        }
+    }
+}
+
+fn no_member(ty: &str, member: &str, name: &str) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::NameError,
+        &format!("{ty} has no {member} '{name}'."),
+    ))
+}
@@ -4,27 +4,83 @@ use std::{
 };
 
 use crate::{
-    error::{Compile, RxError},
-    object::ObjRef,
+    convert::FromValue,
+    error::{ErrorKind, Runtime, RxError},
+    object::{Function, List, Map, Native, ObjRef},
+    userdata::HostObject,
+    Res,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Value {
     Float(f64),
     Int(i64),
     Bool(bool),
     Nil,
     String(ObjRef<String>),
+    Function(ObjRef<Function>),
+    Native(ObjRef<Native>),
+    UserData(ObjRef<HostObject>),
+    List(ObjRef<List>),
+    Map(ObjRef<Map>),
 }
 
 impl Value {
-    fn get_ty(&self) -> &str {
+    pub fn get_ty(&self) -> &str {
         match self {
             Self::Float(_) => "float64",
             Self::Int(_) => "int64",
             Self::Bool(_) => "bool",
             Self::Nil => "nil",
             Self::String(_) => "string",
+            Self::Function(_) => "function",
+            Self::Native(_) => "native function",
+            Self::UserData(u) => u.get().type_name(),
+            Self::List(_) => "list",
+            Self::Map(_) => "map",
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Self::Nil)
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Numeric value of either an int or a float.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            Self::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Contents of a string value, borrowed from this value, which keeps
+    /// the string alive on its own.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s.get()),
+            _ => None,
         }
     }
 }
@@ -38,7 +94,12 @@ impl PartialEq for Value {
             (Self::Nil, Self::Nil) => true,
             (Self::Int(l), Self::Float(r)) => &(*l as f64) == r,
             (Self::Float(l), Self::Int(r)) => l == &(*r as f64),
-            (Self::String(s1), Self::String(s2)) => unsafe { *s1.value == *s2.value },
+            (Self::String(s1), Self::String(s2)) => s1.get() == s2.get(),
+            (Self::Function(f1), Self::Function(f2)) => f1.ptr_eq(f2),
+            (Self::Native(n1), Self::Native(n2)) => n1.ptr_eq(n2),
+            (Self::UserData(u1), Self::UserData(u2)) => u1.ptr_eq(u2),
+            (Self::List(l1), Self::List(l2)) => l1.ptr_eq(l2),
+            (Self::Map(m1), Self::Map(m2)) => m1.ptr_eq(m2),
             _ => false,
         }
     }
@@ -60,102 +121,132 @@ impl PartialOrd for Value {
 }
 
 impl Add for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn add(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Self::Float(l), Self::Float(r)) => Self::Float(l + r),
-            (Self::Int(l), Self::Int(r)) => Self::Int(l + r),
-            (Self::Float(l), Self::Int(r)) => Self::Float(l + *r as f64),
-            (Self::Int(l), Self::Float(r)) => Self::Float(*l as f64 + r),
-            _ => panic!(
-                "TypeError: Unable to add lhs: {} with rhs: {}",
+            (Self::Float(l), Self::Float(r)) => Ok(Self::Float(l + r)),
+            (Self::Int(l), Self::Int(r)) => l
+                .checked_add(*r)
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            (Self::Float(l), Self::Int(r)) => Ok(Self::Float(l + *r as f64)),
+            (Self::Int(l), Self::Float(r)) => Ok(Self::Float(*l as f64 + r)),
+            _ => Err(type_error(&format!(
+                "Unable to add lhs: {} with rhs: {}",
                 self.get_ty(),
                 rhs.get_ty()
-            ),
+            ))),
         }
     }
 }
 
 impl Sub for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn sub(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Self::Float(l), Self::Float(r)) => Self::Float(l - r),
-            (Self::Int(l), Self::Int(r)) => Self::Int(l - r),
-            (Self::Float(l), Self::Int(r)) => Self::Float(l - *r as f64),
-            (Self::Int(l), Self::Float(r)) => Self::Float(*l as f64 - r),
-            _ => panic!(
-                "TypeError: Unable to subtract lhs: {} with rhs: {}",
+            (Self::Float(l), Self::Float(r)) => Ok(Self::Float(l - r)),
+            (Self::Int(l), Self::Int(r)) => l
+                .checked_sub(*r)
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            (Self::Float(l), Self::Int(r)) => Ok(Self::Float(l - *r as f64)),
+            (Self::Int(l), Self::Float(r)) => Ok(Self::Float(*l as f64 - r)),
+            _ => Err(type_error(&format!(
+                "Unable to subtract lhs: {} with rhs: {}",
                 self.get_ty(),
                 rhs.get_ty()
-            ),
+            ))),
         }
     }
 }
 
 impl Mul for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Self::Float(l), Self::Float(r)) => Self::Float(l * r),
-            (Self::Int(l), Self::Int(r)) => Self::Int(l * r),
-            (Self::Float(l), Self::Int(r)) => Self::Float(l * *r as f64),
-            (Self::Int(l), Self::Float(r)) => Self::Float(*l as f64 * r),
-            _ => panic!(
-                "TypeError: Unable to multiply lhs: {} with rhs: {}",
+            (Self::Float(l), Self::Float(r)) => Ok(Self::Float(l * r)),
+            (Self::Int(l), Self::Int(r)) => l
+                .checked_mul(*r)
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            (Self::Float(l), Self::Int(r)) => Ok(Self::Float(l * *r as f64)),
+            (Self::Int(l), Self::Float(r)) => Ok(Self::Float(*l as f64 * r)),
+            _ => Err(type_error(&format!(
+                "Unable to multiply lhs: {} with rhs: {}",
                 self.get_ty(),
                 rhs.get_ty()
-            ),
+            ))),
         }
     }
 }
 
 impl Div for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn div(self, rhs: Self) -> Self::Output {
         match (&self, &rhs) {
-            (Self::Float(l), Self::Float(r)) => Self::Float(l / r),
-            (Self::Int(l), Self::Int(r)) => Self::Int(l / r),
-            (Self::Float(l), Self::Int(r)) => Self::Float(l / *r as f64),
-            (Self::Int(l), Self::Float(r)) => Self::Float(*l as f64 / r),
-            _ => panic!(
-                "TypeError: Unable to divide lhs: {} with rhs: {}",
+            (Self::Float(l), Self::Float(r)) => Ok(Self::Float(l / r)),
+            (Self::Int(_), Self::Int(0)) => Err(arithmetic_error("Division by zero")),
+            (Self::Int(l), Self::Int(r)) => l
+                .checked_div(*r)
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            (Self::Float(l), Self::Int(r)) => Ok(Self::Float(l / *r as f64)),
+            (Self::Int(l), Self::Float(r)) => Ok(Self::Float(*l as f64 / r)),
+            _ => Err(type_error(&format!(
+                "Unable to divide lhs: {} with rhs: {}",
                 self.get_ty(),
                 rhs.get_ty()
-            ),
+            ))),
         }
     }
 }
 
 impl Neg for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn neg(self) -> Self::Output {
         match self {
-            Self::Float(f) => Self::Float(-f),
-            Self::Int(i) => Self::Int(-i),
-            _ => panic!("TypeError: Unable to negate {}", self.get_ty()),
+            Self::Float(f) => Ok(Self::Float(-f)),
+            Self::Int(i) => i
+                .checked_neg()
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            _ => Err(type_error(&format!("Unable to negate {}", self.get_ty()))),
         }
     }
 }
 
 impl Not for Value {
-    type Output = Self;
+    type Output = Res<Self>;
 
     fn not(self) -> Self::Output {
         match self {
-            Self::Int(i) => Self::Int(-i),
-            Self::Bool(b) => Self::Bool(!b),
-            Self::Nil => Self::Bool(false),
-            _ => panic!("TypeError: Unable to use logical not on {}", self.get_ty()),
+            Self::Int(i) => i
+                .checked_neg()
+                .map(Self::Int)
+                .ok_or_else(|| arithmetic_error("Integer overflow")),
+            Self::Bool(b) => Ok(Self::Bool(!b)),
+            Self::Nil => Ok(Self::Bool(false)),
+            _ => Err(type_error(&format!(
+                "Unable to use logical not on {}",
+                self.get_ty()
+            ))),
         }
     }
 }
 
+fn type_error(msg: &str) -> RxError {
+    RxError::new(Runtime::new(ErrorKind::TypeError, msg))
+}
+
+fn arithmetic_error(msg: &str) -> RxError {
+    RxError::new(Runtime::new(ErrorKind::ArithmeticError, msg))
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -164,6 +255,11 @@ impl fmt::Display for Value {
             Self::Bool(b) => write!(f, "{b}"),
             Self::Nil => write!(f, "nil"),
             Self::String(v) => write!(f, "{}", v),
+            Self::Function(v) => write!(f, "{}", v),
+            Self::Native(v) => write!(f, "{}", v),
+            Self::UserData(v) => write!(f, "{}", v),
+            Self::List(v) => write!(f, "{}", v),
+            Self::Map(v) => write!(f, "{}", v),
         }
     }
 }
@@ -178,13 +274,7 @@ impl TryFrom<Value> for f64 {
     type Error = RxError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Float(f) => Ok(f),
-            _ => Err(RxError::new(Compile::new(&format!(
-                "TypeError: Unable to convert {} to an f64",
-                value.get_ty()
-            )))),
-        }
+        f64::from_value(value)
     }
 }
 
@@ -198,13 +288,7 @@ impl TryFrom<Value> for i64 {
     type Error = RxError;
 
     fn try_from(value: Value) -> Result<Self, Self::Error> {
-        match value {
-            Value::Int(i) => Ok(i),
-            _ => Err(RxError::new(Compile::new(&format!(
-                "TypeError: Unable to convert {} to an i64",
-                value.get_ty()
-            )))),
-        }
+        i64::from_value(value)
     }
 }
 
@@ -0,0 +1,66 @@
+use std::io::{self, BufRead, BufReader, Write};
+
+use super::{Limits, Tracer, Vm};
+
+/// Configures the limits, I/O handles and tracing of a `Vm`.
+///
+/// ```
+/// use roxy::vm::{SharedBuffer, Vm};
+///
+/// let out = SharedBuffer::new();
+/// let vm = Vm::builder().stdout(out.clone()).build();
+/// ```
+#[derive(Default)]
+pub struct VmBuilder {
+    limits: Limits,
+    stdout: Option<Box<dyn Write + Send>>,
+    stderr: Option<Box<dyn Write + Send>>,
+    stdin: Option<Box<dyn BufRead + Send>>,
+    tracer: Option<Tracer>,
+}
+
+impl VmBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn stdout<W: Write + Send + 'static>(mut self, stdout: W) -> Self {
+        self.stdout = Some(Box::new(stdout));
+        self
+    }
+
+    pub fn stderr<W: Write + Send + 'static>(mut self, stderr: W) -> Self {
+        self.stderr = Some(Box::new(stderr));
+        self
+    }
+
+    pub fn stdin<R: BufRead + Send + 'static>(mut self, stdin: R) -> Self {
+        self.stdin = Some(Box::new(stdin));
+        self
+    }
+
+    pub fn tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    pub fn build(self) -> Vm {
+        // the REPL only edits lines when talking to the process's terminal
+        let terminal = self.stdin.is_none() && self.stdout.is_none();
+        let mut vm = Vm::from_parts(
+            self.limits,
+            self.stdout.unwrap_or_else(|| Box::new(io::stdout())),
+            self.stderr.unwrap_or_else(|| Box::new(io::stderr())),
+            self.stdin
+                .unwrap_or_else(|| Box::new(BufReader::new(io::stdin()))),
+            terminal,
+        );
+        vm.set_tracer(self.tracer);
+        vm
+    }
+}
@@ -0,0 +1,461 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+use super::terminal_width;
+
+const MAX_HISTORY: usize = 1000;
+
+/// Outcome of `LineEditor::read_line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadLine {
+    Line(String),
+    /// Ctrl-C, discarding the line.
+    Interrupted,
+    /// Ctrl-D on an empty line, or the end of the input.
+    Eof,
+}
+
+/// Line editor for the REPL, reading keys from a terminal in raw mode.
+///
+/// Supports cursor movement with the arrow keys, Home, End and the usual
+/// Emacs bindings, history with Up and Down, and incremental history
+/// search with Ctrl-R. Entries are appended to a history file if given.
+///
+/// ```
+/// use roxy::vm::{LineEditor, ReadLine};
+///
+/// let mut editor = LineEditor::new(None);
+/// let mut out = Vec::new();
+/// let keys = b"pint\x1b[D\x1b[D\x1b[Dr\r";
+/// let line = editor.read_line("> ", &mut &keys[..], &mut out).unwrap();
+/// assert_eq!(line, ReadLine::Line("print".to_owned()));
+/// ```
+pub struct LineEditor {
+    history: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+/// `$ROXY_HISTORY`, or `.roxy_history` in the home directory.
+pub fn history_file() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("ROXY_HISTORY") {
+        return (!path.is_empty()).then(|| PathBuf::from(path));
+    }
+
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(".roxy_history"))
+}
+
+impl LineEditor {
+    /// Creates an editor, loading the history from `path` if it exists.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut history = Vec::new();
+        if let Some(contents) = path.as_ref().and_then(|p| fs::read_to_string(p).ok()) {
+            history.extend(contents.lines().map(str::to_owned));
+        }
+
+        let mut editor = Self { history, path };
+        if editor.history.len() > MAX_HISTORY {
+            editor.history.drain(..editor.history.len() - MAX_HISTORY);
+            editor.save();
+        }
+        editor
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Remembers `line`, unless it is blank or repeats the last entry.
+    /// Failing to write the history file is not an error.
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+
+        self.history.push(line.to_owned());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+
+        if let Some(path) = &self.path {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            let _ = file.and_then(|mut file| writeln!(file, "{line}"));
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            let mut contents = self.history.join("\n");
+            contents.push('\n');
+            let _ = fs::write(path, contents);
+        }
+    }
+
+    /// Reads one line, echoing and redrawing it on `out`.
+    ///
+    /// `input` should be a terminal in raw mode, see `RawMode`.
+    pub fn read_line(
+        &mut self,
+        prompt: &str,
+        input: &mut dyn Read,
+        out: &mut dyn Write,
+    ) -> io::Result<ReadLine> {
+        let mut line = Line {
+            prompt,
+            buf: Vec::new(),
+            pos: 0,
+            width: terminal_width().unwrap_or(80),
+        };
+        let mut entry = self.history.len();
+        let mut draft = Vec::new();
+        let mut search: Option<Search> = None;
+
+        line.refresh(out)?;
+        loop {
+            let Some(key) = read_key(input)? else {
+                return Ok(match line.buf.is_empty() {
+                    true => ReadLine::Eof,
+                    false => ReadLine::Line(line.text()),
+                });
+            };
+
+            if let Some(active) = &mut search {
+                match key {
+                    Key::Ctrl(b'r') => active.find(&self.history, true),
+                    Key::Char(c) => {
+                        active.query.push(c);
+                        active.find(&self.history, false);
+                    }
+                    Key::Backspace => {
+                        active.query.pop();
+                        active.found = None;
+                        active.find(&self.history, false);
+                    }
+                    Key::Ctrl(b'g') | Key::Ctrl(b'c') => {
+                        line.buf = search.take().map(|s| s.original).unwrap_or_default();
+                        line.pos = line.buf.len();
+                        line.refresh(out)?;
+                        continue;
+                    }
+                    _ => {
+                        // any other key keeps the match and is handled below
+                        if let Some(idx) = active.found {
+                            line.buf = self.history[idx].chars().collect();
+                            line.pos = line.buf.len();
+                            entry = idx;
+                        }
+                        search = None;
+                        line.refresh(out)?;
+                    }
+                }
+
+                if let Some(active) = &search {
+                    active.refresh(&self.history, &line, out)?;
+                    continue;
+                }
+            }
+
+            match key {
+                Key::Enter => {
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(ReadLine::Line(line.text()));
+                }
+                Key::Ctrl(b'c') => {
+                    write!(out, "^C\r\n")?;
+                    out.flush()?;
+                    return Ok(ReadLine::Interrupted);
+                }
+                Key::Ctrl(b'd') if line.buf.is_empty() => {
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(ReadLine::Eof);
+                }
+                Key::Ctrl(b'r') => {
+                    let active = Search {
+                        query: String::new(),
+                        found: None,
+                        original: line.buf.clone(),
+                    };
+                    active.refresh(&self.history, &line, out)?;
+                    search = Some(active);
+                    continue;
+                }
+
+                Key::Char(c) => {
+                    line.buf.insert(line.pos, c);
+                    line.pos += 1;
+                }
+                Key::Tab => {
+                    for _ in 0..4 {
+                        line.buf.insert(line.pos, ' ');
+                    }
+                    line.pos += 4;
+                }
+                Key::Backspace | Key::Ctrl(b'h') if line.pos > 0 => {
+                    line.pos -= 1;
+                    line.buf.remove(line.pos);
+                }
+                Key::Delete | Key::Ctrl(b'd') if line.pos < line.buf.len() => {
+                    line.buf.remove(line.pos);
+                }
+                Key::Left | Key::Ctrl(b'b') => line.pos = line.pos.saturating_sub(1),
+                Key::Right | Key::Ctrl(b'f') => line.pos = (line.pos + 1).min(line.buf.len()),
+                Key::Home | Key::Ctrl(b'a') => line.pos = 0,
+                Key::End | Key::Ctrl(b'e') => line.pos = line.buf.len(),
+                Key::WordLeft => line.pos = line.word_start(),
+                Key::WordRight => line.pos = line.word_end(),
+                Key::Ctrl(b'w') => {
+                    let start = line.word_start();
+                    line.buf.drain(start..line.pos);
+                    line.pos = start;
+                }
+                Key::Ctrl(b'k') => line.buf.truncate(line.pos),
+                Key::Ctrl(b'u') => {
+                    line.buf.drain(..line.pos);
+                    line.pos = 0;
+                }
+                Key::Ctrl(b'l') => write!(out, "\x1b[H\x1b[2J")?,
+
+                Key::Up | Key::Ctrl(b'p') if entry > 0 => {
+                    if entry == self.history.len() {
+                        draft = line.buf.clone();
+                    }
+                    entry -= 1;
+                    line.buf = self.history[entry].chars().collect();
+                    line.pos = line.buf.len();
+                }
+                Key::Down | Key::Ctrl(b'n') if entry < self.history.len() => {
+                    entry += 1;
+                    line.buf = match self.history.get(entry) {
+                        Some(text) => text.chars().collect(),
+                        None => draft.clone(),
+                    };
+                    line.pos = line.buf.len();
+                }
+                _ => continue,
+            }
+            line.refresh(out)?;
+        }
+    }
+}
+
+/// The line being edited.
+struct Line<'p> {
+    prompt: &'p str,
+    buf: Vec<char>,
+    pos: usize,
+    width: usize,
+}
+
+impl Line<'_> {
+    fn text(&self) -> String {
+        self.buf.iter().collect()
+    }
+
+    /// Redraws the line, scrolling it sideways if it does not fit.
+    fn refresh(&self, out: &mut dyn Write) -> io::Result<()> {
+        draw(out, self.prompt, &self.buf, self.pos, self.width)
+    }
+
+    fn word_start(&self) -> usize {
+        let mut pos = self.pos;
+        while pos > 0 && self.buf[pos - 1].is_whitespace() {
+            pos -= 1;
+        }
+        while pos > 0 && !self.buf[pos - 1].is_whitespace() {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn word_end(&self) -> usize {
+        let mut pos = self.pos;
+        while pos < self.buf.len() && self.buf[pos].is_whitespace() {
+            pos += 1;
+        }
+        while pos < self.buf.len() && !self.buf[pos].is_whitespace() {
+            pos += 1;
+        }
+        pos
+    }
+}
+
+fn draw(
+    out: &mut dyn Write,
+    prompt: &str,
+    buf: &[char],
+    pos: usize,
+    width: usize,
+) -> io::Result<()> {
+    let prompt_width = prompt.chars().count();
+    let room = width.saturating_sub(prompt_width + 1).max(1);
+    let start = pos.saturating_sub(room);
+    let end = buf.len().min(start + room);
+
+    let visible = buf[start..end].iter().collect::<String>();
+    write!(out, "\r{prompt}{visible}\x1b[K\r")?;
+    let column = prompt_width + pos - start;
+    if column > 0 {
+        write!(out, "\x1b[{column}C")?;
+    }
+    out.flush()
+}
+
+/// State of a Ctrl-R search.
+struct Search {
+    query: String,
+    /// History entry the query last matched.
+    found: Option<usize>,
+    /// Line to restore when the search is cancelled.
+    original: Vec<char>,
+}
+
+impl Search {
+    /// Looks for the query from the newest entry backwards, starting
+    /// before the current match if `older`.
+    fn find(&mut self, history: &[String], older: bool) {
+        let end = match (self.found, older) {
+            (Some(idx), true) => idx,
+            (Some(idx), false) => idx + 1,
+            (None, _) => history.len(),
+        };
+        if let Some(idx) = history[..end]
+            .iter()
+            .rposition(|entry| entry.contains(&self.query))
+        {
+            self.found = Some(idx);
+        } else if !older {
+            self.found = None;
+        }
+    }
+
+    fn failed(&self, history: &[String]) -> bool {
+        !self.query.is_empty()
+            && self
+                .found
+                .is_none_or(|idx| !history[idx].contains(&self.query))
+    }
+
+    fn refresh(&self, history: &[String], line: &Line, out: &mut dyn Write) -> io::Result<()> {
+        let failed = if self.failed(history) { "failed " } else { "" };
+        let prompt = format!("({failed}reverse-i-search)'{}': ", self.query);
+        // the cursor sits at the start of the match
+        let (matched, pos) = match self.found {
+            Some(idx) => {
+                let entry = &history[idx];
+                let byte = entry.find(&self.query).unwrap_or(entry.len());
+                (entry.chars().collect(), entry[..byte].chars().count())
+            }
+            None => (line.buf.clone(), line.buf.len()),
+        };
+        draw(out, &prompt, &matched, pos, line.width)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    /// Control character, by the lowercase letter typed with Ctrl.
+    Ctrl(u8),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    WordLeft,
+    WordRight,
+    Unknown,
+}
+
+fn read_byte(input: &mut dyn Read) -> io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Decodes the next key press, `None` at the end of the input.
+fn read_key(input: &mut dyn Read) -> io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f => Key::Backspace,
+        0x1b => read_escape(input)?,
+        0x01..=0x1a => Key::Ctrl(byte - 1 + b'a'),
+        0x00..=0x1f => Key::Unknown,
+        _ => {
+            // the rest of a UTF-8 sequence
+            let len = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(read_byte(input)?);
+            }
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(c) => Key::Char(c),
+                None => Key::Unknown,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+/// Decodes `ESC [ ...`, `ESC O ...` and Alt sequences.
+fn read_escape(input: &mut dyn Read) -> io::Result<Key> {
+    let key = match read_byte(input)? {
+        Some(b'[') | Some(b'O') => {
+            let mut params = Vec::new();
+            let final_byte = loop {
+                match read_byte(input)? {
+                    Some(b @ 0x40..=0x7e) => break b,
+                    Some(b) => params.push(b),
+                    None => return Ok(Key::Unknown),
+                }
+            };
+
+            let ctrl = params.ends_with(b";5");
+            match (final_byte, params.as_slice()) {
+                (b'C', _) if ctrl => Key::WordRight,
+                (b'D', _) if ctrl => Key::WordLeft,
+                (b'A', _) => Key::Up,
+                (b'B', _) => Key::Down,
+                (b'C', _) => Key::Right,
+                (b'D', _) => Key::Left,
+                (b'H', _) | (b'~', b"1") | (b'~', b"7") => Key::Home,
+                (b'F', _) | (b'~', b"4") | (b'~', b"8") => Key::End,
+                (b'~', b"3") => Key::Delete,
+                _ => Key::Unknown,
+            }
+        }
+        Some(b'b') => Key::WordLeft,
+        Some(b'f') => Key::WordRight,
+        _ => Key::Unknown,
+    };
+    Ok(key)
+}
@@ -1,31 +1,115 @@
 use crate::{
-    chunks::{Chunk, Opcode},
-    compiler::{Parser, TokenType},
-    object::ObjRef,
+    chunks::{assemble, Chunk, Opcode},
+    compiler::{self, tokenize, TokenType},
+    convert::NativeSignature,
+    error::{ErrorKind, Runtime, RxError},
+    object::{Function, Heap, HeapStats, List, Map, Native, ObjRef},
+    userdata::{HostObject, UserData},
     value::Value,
     Res,
 };
 use std::{
-    borrow::BorrowMut,
+    collections::HashMap,
     fs,
-    io::{self, BufRead, Read, Write},
+    io::{self, BufRead, IsTerminal, Read, Write},
 };
 
-pub struct Vm<'src> {
-    chunk: &'src mut Chunk,
-    stack: Vec<Value>,
-    ip: usize,
+use super::{
+    catch_sigint, history_file, natives, CallFrame, FrameKind, Interrupt, Limits, LineEditor,
+    RawMode, ReadLine, Stack, Tracer, VmBuilder,
+};
+
+pub struct Vm {
+    stack: Stack,
+    frames: Vec<CallFrame>,
+    globals: HashMap<String, Value>,
+    heap: Heap,
+    limits: Limits,
+    fuel: Option<u64>,
+    interrupt: Interrupt,
+    tracer: Option<Tracer>,
+
+    stdout: Box<dyn Write + Send>,
+    stderr: Box<dyn Write + Send>,
+    stdin: Box<dyn BufRead + Send>,
+    /// Whether the streams are the process's own, which may be a terminal.
+    terminal: bool,
 }
 
-impl<'src> Vm<'src> {
-    const STACK_SIZE: usize = u8::MAX as usize + 1;
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    pub fn new(chunk: &'src mut Chunk) -> Self {
-        Vm {
-            chunk,
-            stack: Vec::with_capacity(Self::STACK_SIZE),
-            ip: 0,
-        }
+impl Vm {
+    const SCRIPT: &'static str = "<script>";
+    const REPL: &'static str = "<repl>";
+    const HOST: &'static str = "<host>";
+
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn with_limits(limits: Limits) -> Self {
+        Self::builder().limits(limits).build()
+    }
+
+    pub fn builder() -> VmBuilder {
+        VmBuilder::new()
+    }
+
+    pub(super) fn from_parts(
+        limits: Limits,
+        stdout: Box<dyn Write + Send>,
+        stderr: Box<dyn Write + Send>,
+        stdin: Box<dyn BufRead + Send>,
+        terminal: bool,
+    ) -> Self {
+        let mut vm = Vm {
+            stack: Stack::new(limits.max_stack),
+            frames: Vec::with_capacity(limits.max_frames),
+            globals: HashMap::new(),
+            heap: Heap::new(limits.max_heap, limits.max_string_len),
+            limits,
+            fuel: None,
+            interrupt: Interrupt::new(),
+            tracer: None,
+
+            stdout,
+            stderr,
+            stdin,
+            terminal,
+        };
+        natives::register(&mut vm);
+        vm
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    /// Output handle scripts and natives print to.
+    pub fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    pub fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.stderr
+    }
+
+    /// Starts or, with `None`, stops tracing executed instructions.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    /// Handle that aborts the current run when triggered, from any thread.
+    pub fn interrupt_handle(&self) -> Interrupt {
+        self.interrupt.clone()
     }
 
     // runners
@@ -34,157 +118,624 @@ impl<'src> Vm<'src> {
         let mut buf = String::new();
 
         file.read_to_string(&mut buf)?;
-        self.interpret(&buf)?;
+        if file_name.ends_with(".rxasm") {
+            let script = assemble(&buf, file_name, &mut self.heap)?;
+            let script = self.heap.alloc_function(script)?;
+            self.run_script(script)?;
+        } else {
+            self.execute(file_name, &buf)?;
+        }
         Ok(())
     }
 
+    /// Reads, evaluates and prints lines until `q` or the end of the input.
+    ///
+    /// Lines leaving a `{`, `(` or string open are continued on the next
+    /// one. On a terminal, lines are edited with `LineEditor` and kept in
+    /// the `history_file`.
     pub fn run_repl(&mut self) -> Res<()> {
+        let _sigint = catch_sigint(&self.interrupt);
+
+        let interactive = self.terminal && io::stdin().is_terminal() && io::stdout().is_terminal();
+        let mut editor = interactive.then(|| LineEditor::new(history_file()));
+
         let mut input = String::new();
         loop {
-            input.clear();
-            print!("roxy:> ");
-            let _ = io::stdout().lock().flush();
-            if let Err(err) = io::stdin().lock().read_line(&mut input) {
-                eprintln!("RoxyUnwind: {err}");
-                continue;
+            let prompt = if input.is_empty() {
+                "roxy:> "
+            } else {
+                "  ...> "
+            };
+            let line = match editor.as_mut() {
+                Some(editor) => {
+                    let _raw = RawMode::enable()?;
+                    editor.read_line(prompt, &mut self.stdin, &mut self.stdout)?
+                }
+                None => self.read_line(prompt)?,
+            };
+
+            let line = match line {
+                ReadLine::Line(line) => line,
+                ReadLine::Interrupted => {
+                    input.clear();
+                    continue;
+                }
+                ReadLine::Eof => break,
+            };
+            if let Some(editor) = editor.as_mut() {
+                editor.add_history(&line);
             }
 
-            let check = input.trim();
-            if check == "q" {
-                break;
-            } else if check.is_empty() {
+            if input.is_empty() {
+                match line.trim() {
+                    "q" => break,
+                    "" => continue,
+                    _ => (),
+                }
+            }
+            input.push_str(&line);
+            input.push('\n');
+            if is_incomplete(&input) {
                 continue;
             }
 
-            self.interpret(check)?;
-            // match self.execute(check) {
-            //     Ok(_) => continue,
-            //     Err(e) => {
-            //         eprintln!("RoxyUnwind: {e}");
-            //         continue;
-            //     }
-            // }
+            // a Ctrl-C at the prompt should not cancel the next line
+            self.interrupt.reset();
+            match self.execute(Self::REPL, input.trim()) {
+                Ok(Value::Nil) => (),
+                Ok(value) => writeln!(self.stdout, "{value}")?,
+                Err(e) => writeln!(self.stderr, "RoxyUnwind: {e}")?,
+            }
+            input.clear();
         }
-        println!("Exiting...");
+        writeln!(self.stdout, "Exiting...")?;
         Ok(())
     }
 
+    /// Reads a line from `stdin` without editing, for input that is not
+    /// a terminal.
+    fn read_line(&mut self, prompt: &str) -> Res<ReadLine> {
+        let mut line = String::new();
+        loop {
+            write!(self.stdout, "{prompt}")?;
+            self.stdout.flush()?;
+            match self.stdin.read_line(&mut line) {
+                Ok(0) => return Ok(ReadLine::Eof),
+                Ok(_) => break,
+                Err(err) => writeln!(self.stderr, "RoxyUnwind: {err}")?,
+            }
+        }
+
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(ReadLine::Line(line))
+    }
+
     // misc
-    fn push(&mut self, value: Value) {
-        self.stack.push(value);
+    fn push(&mut self, value: Value) -> Res<()> {
+        self.stack.push(value)
+    }
+
+    fn pop(&mut self) -> Res<Value> {
+        self.stack.pop()
     }
 
-    fn pop(&mut self) -> Value {
-        self.stack.pop().expect("Empty stack")
+    fn frame(&mut self) -> &mut CallFrame {
+        self.frames.last_mut().expect("No active call frame")
     }
 
-    // fn peek(&self, distance: usize) -> &Value {
-    //     &self.stack[self.stack.len() - 1 - distance]
-    // }
+    fn tick(&mut self) -> Res<()> {
+        if self.interrupt.is_triggered() {
+            self.interrupt.reset();
+            return Err(RxError::new(Runtime::new(
+                ErrorKind::Interrupted,
+                "Execution interrupted",
+            )));
+        }
+
+        if let Some(fuel) = self.fuel.as_mut() {
+            if *fuel == 0 {
+                return Err(RxError::new(Runtime::new(
+                    ErrorKind::BudgetExceeded,
+                    &format!(
+                        "Instruction budget of {} exhausted",
+                        self.limits.max_instructions.unwrap_or_default()
+                    ),
+                )));
+            }
+            *fuel -= 1;
+        }
+
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: CallFrame) -> Res<()> {
+        if self.frames.len() >= self.limits.max_frames {
+            return Err(RxError::new(Runtime::new(
+                ErrorKind::StackOverflow,
+                &format!("Call depth exceeded {} frames", self.limits.max_frames),
+            )));
+        }
+
+        self.frames.push(frame);
+        Ok(())
+    }
 
     pub fn interpret(&mut self, buf: &str) -> Res<()> {
-        let parser = Parser::new(buf, self.chunk.borrow_mut());
-        parser.compile();
+        self.eval(buf).map(|_| ())
+    }
 
-        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.run()));
+    /// Compiles and runs `buf`, returning the value the script produced.
+    pub fn eval(&mut self, buf: &str) -> Res<Value> {
+        self.execute(Self::SCRIPT, buf)
+    }
 
-        match result {
-            Ok(_) => (),
-            Err(e) => {
-                self.runtime_error(
-                    e.downcast_ref::<String>()
-                        .unwrap_or(&"typed error occured.. Resetting stack.. Fallback".to_string())
-                        .to_owned(),
-                );
-            }
+    /// Like `eval`, reporting errors as coming from the file `name`.
+    pub fn eval_source(&mut self, name: &str, buf: &str) -> Res<Value> {
+        self.execute(name, buf)
+    }
+
+    /// Assembles `.rxasm` text, see `chunks::assemble`, and runs it.
+    pub fn eval_asm(&mut self, name: &str, buf: &str) -> Res<Value> {
+        let script = assemble(buf, name, &mut self.heap)?;
+        let script = self.heap.alloc_function(script)?;
+        self.run_script(script)
+    }
+
+    fn execute(&mut self, file: &str, buf: &str) -> Res<Value> {
+        let script = compiler::compile(buf, file, &mut self.heap)?;
+        self.run_script(script)
+    }
+
+    /// Runs `script` on top of whatever is running, so a native can eval
+    /// code re-entrantly; errors unwind back to the caller's frames.
+    fn run_script(&mut self, script: ObjRef<Function>) -> Res<Value> {
+        let (frames, slots) = (self.frames.len(), self.stack.len());
+        if frames == 0 {
+            self.fuel = self.limits.max_instructions;
+        }
+
+        let result = self
+            .push(Value::Function(script.clone()))
+            .and_then(|_| self.push_frame(CallFrame::new(FrameKind::Script, script, slots)))
+            .and_then(|_| self.run(frames));
+
+        if result.is_err() {
+            self.frames.truncate(frames);
+            self.stack.truncate(slots);
         }
+        result
+    }
+
+    // host calls
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.get(name).cloned()
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.insert(name.to_owned(), value);
+    }
+
+    /// Exposes a host function to scripts as the global `name`.
+    ///
+    /// `arity` is checked before every call; `None` accepts any number of
+    /// arguments.
+    pub fn register_native<F>(&mut self, name: &str, arity: Option<u8>, function: F) -> Res<()>
+    where
+        F: Fn(&mut Vm, &[Value]) -> Res<Value> + Send + Sync + 'static,
+    {
+        let native = self.heap.alloc_native(Native {
+            name: name.into(),
+            arity,
+            function: Box::new(function),
+        })?;
+        self.set_global(name, Value::Native(native));
         Ok(())
     }
 
-    pub fn run(&mut self) -> Res<()> {
-        loop {
-            let op = &self.chunk.code[self.ip];
+    /// Registers a Rust function with a typed signature as a native.
+    ///
+    /// Arguments are converted with `FromValue` and the result with
+    /// `IntoValue`, so `|a: i64, b: i64| a + b` can be registered as is.
+    pub fn register_fn<Args, F>(&mut self, name: &str, function: F) -> Res<()>
+    where
+        F: NativeSignature<Args> + Send + Sync + 'static,
+    {
+        self.register_native(name, Some(F::arity()), move |vm, args| {
+            function.invoke(vm, args)
+        })
+    }
+
+    /// Allocates a string on this VM's heap.
+    pub fn new_string(&mut self, string: &str) -> Res<Value> {
+        self.heap.alloc_string(string.to_owned()).map(Value::String)
+    }
 
-            #[cfg(feature = "trace")]
-            {
-                let disassembler = crate::chunks::Disassembler::new(&self.chunk, Some(&self.stack));
-                disassembler.instruction(self.ip, op);
+    pub fn new_list(&mut self, items: Vec<Value>) -> Res<Value> {
+        self.heap.alloc_list(List { items }).map(Value::List)
+    }
+
+    pub fn new_map(&mut self, entries: HashMap<String, Value>) -> Res<Value> {
+        self.heap.alloc_map(Map { entries }).map(Value::Map)
+    }
+
+    /// Copies a value obtained from another VM into this one.
+    ///
+    /// Values are reference counted and `Send`, so handing one to another
+    /// VM, even on another thread, is always memory safe. Importing it also
+    /// moves strings, lists and maps onto this VM's heap, so they count
+    /// against its limits and no longer keep the other VM's objects alive.
+    /// Functions, natives and userdata are immutable or locked, and stay
+    /// shared.
+    pub fn import(&mut self, value: &Value) -> Res<Value> {
+        match value {
+            Value::String(string) => self.new_string(string.get()),
+            Value::List(list) => {
+                let items = list
+                    .get()
+                    .items
+                    .iter()
+                    .map(|item| self.import(item))
+                    .collect::<Res<Vec<_>>>()?;
+                self.new_list(items)
+            }
+            Value::Map(map) => {
+                let entries = map
+                    .get()
+                    .entries
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.import(value)?)))
+                    .collect::<Res<HashMap<_, _>>>()?;
+                self.new_map(entries)
             }
+            _ => Ok(value.clone()),
+        }
+    }
 
-            self.ip += 1;
+    /// Moves a host value onto this VM's heap so scripts can use it.
+    pub fn new_userdata<T: UserData + 'static>(&mut self, data: T) -> Res<Value> {
+        self.heap
+            .alloc_userdata(HostObject::new(data))
+            .map(Value::UserData)
+    }
 
-            match *op {
-                Opcode::Constant(constant) => {
-                    let value = self.chunk.read_constant(constant);
-                    self.push(value);
-                }
-                Opcode::Nil => self.push(Value::Nil),
-                Opcode::True => self.push(Value::Bool(true)),
-                Opcode::False => self.push(Value::Bool(false)),
-                Opcode::Equal => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(Value::Bool(l == r))
-                }
-                Opcode::Greater => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(Value::Bool(l > r))
-                }
-                Opcode::Less => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(Value::Bool(l < r))
-                }
-                Opcode::Add => {
-                    let val = match (self.pop(), self.pop()) {
-                        (Value::String(r), Value::String(l)) => {
-                            let str = format!("{}{}", l, r);
-                            Value::String(ObjRef::new(Box::into_raw(Box::new(str))))
-                        }
-                        (r, l) => l + r,
-                    };
-                    self.push(val);
-                }
-                Opcode::Subtract => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(l - r);
+    /// Calls the function stored in the global `name` with `args`.
+    pub fn call_global(&mut self, name: &str, args: &[Value]) -> Res<Value> {
+        match self.get_global(name) {
+            Some(callee) => self.call(callee, args),
+            None => Err(RxError::new(Runtime::new(
+                ErrorKind::NameError,
+                &format!("Undefined function '{name}'."),
+            ))),
+        }
+    }
+
+    /// Calls a roxy or native function value from host code.
+    ///
+    /// This may be used re-entrantly from inside a native function; errors
+    /// unwind back to the state the VM was in before the call.
+    pub fn call(&mut self, callee: Value, args: &[Value]) -> Res<Value> {
+        if self.frames.is_empty() {
+            self.fuel = self.limits.max_instructions;
+        }
+
+        let (frames, stack) = (self.frames.len(), self.stack.len());
+        let result = self.call_from_host(callee, args);
+        if result.is_err() {
+            self.frames.truncate(frames);
+            self.stack.truncate(stack);
+        }
+        result
+    }
+
+    fn call_from_host(&mut self, callee: Value, args: &[Value]) -> Res<Value> {
+        let slots = self.stack.len();
+        self.push_frame(CallFrame::native(Self::HOST.into(), slots))?;
+        let depth = self.frames.len();
+
+        self.push(callee.clone())?;
+        for arg in args {
+            self.push(arg.clone())?;
+        }
+
+        if let Err(e) = self.call_value(callee, args.len() as u8) {
+            return Err(self.traced(e));
+        }
+
+        // natives have already left their result on the stack
+        let result = if self.frames.len() > depth {
+            self.run(depth)?
+        } else {
+            self.pop()?
+        };
+
+        self.frames.pop();
+        Ok(result)
+    }
+
+    fn call_value(&mut self, callee: Value, argc: u8) -> Res<()> {
+        match callee {
+            Value::Function(function) => {
+                let arity = function.get().arity;
+                if argc != arity {
+                    return Err(arity_error(&function.get().name, arity, argc));
                 }
-                Opcode::Multiply => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(l * r);
+
+                let slots = self.stack.len() - argc as usize - 1;
+                self.push_frame(CallFrame::new(FrameKind::Function, function, slots))
+            }
+            Value::Native(native) => {
+                let native = native.get();
+                if let Some(arity) = native.arity {
+                    if argc != arity {
+                        return Err(arity_error(&native.name, arity, argc));
+                    }
                 }
-                Opcode::Divide => {
-                    let (r, l) = (self.pop(), self.pop());
-                    self.push(l / r);
+
+                let slots = self.stack.len() - argc as usize - 1;
+                let args = self.stack.as_slice()[slots + 1..].to_vec();
+
+                self.push_frame(CallFrame::native(native.name.clone(), slots))?;
+                // on error the frame stays so it shows up in the trace
+                let result = (native.function)(self, &args)?;
+                self.frames.pop();
+
+                self.stack.truncate(slots);
+                self.push(result)
+            }
+            _ => Err(RxError::new(Runtime::new(
+                ErrorKind::TypeError,
+                &format!("Can only call functions, not {}.", callee.get_ty()),
+            ))),
+        }
+    }
+
+    fn invoke(&mut self, name: &str, argc: u8) -> Res<()> {
+        let object = match self.stack.peek(argc as usize)? {
+            Value::UserData(object) => object.clone(),
+            other => return Err(not_an_object(other)),
+        };
+
+        let slots = self.stack.len() - argc as usize - 1;
+        let args = self.stack.as_slice()[slots + 1..].to_vec();
+
+        let frame_name = format!("{}.{}", object.get().type_name(), name);
+        self.push_frame(CallFrame::native(frame_name.into(), slots))?;
+        // on error the frame stays so it shows up in the trace
+        let result = object.get().call_method(self, name, &args)?;
+        self.frames.pop();
+
+        self.stack.truncate(slots);
+        self.push(result)
+    }
+
+    /// Runs until the frame stack shrinks back to `depth`.
+    fn run(&mut self, depth: usize) -> Res<Value> {
+        loop {
+            match self.step(depth) {
+                Ok(Some(value)) => return Ok(value),
+                Ok(None) => (),
+                Err(e) => return Err(self.traced(e)),
+            }
+        }
+    }
+
+    fn step(&mut self, depth: usize) -> Res<Option<Value>> {
+        self.tick()?;
+
+        let frame = self.frame();
+        let (ip, slots) = (frame.ip, frame.slots);
+        let function = frame
+            .function
+            .clone()
+            .expect("Native frames have no bytecode");
+        let chunk = &function.get().chunk;
+        let Some(&op) = chunk.code.get(ip) else {
+            // only hand-written chunks can lack a final OP_Return
+            return Err(RxError::new(Runtime::new(
+                ErrorKind::InvalidBytecode,
+                &format!("Ran past the end of {}", function.get()),
+            )));
+        };
+
+        if let Some(tracer) = &mut self.tracer {
+            let name = &function.get().name;
+            tracer.instruction(name, chunk, ip, &op, self.stack.as_slice())?;
+        }
+
+        self.frame().ip += 1;
+
+        match op {
+            Opcode::Constant(constant) => {
+                let value = chunk.read_constant(constant);
+                self.push(value)?;
+            }
+            Opcode::Nil => self.push(Value::Nil)?,
+            Opcode::True => self.push(Value::Bool(true))?,
+            Opcode::False => self.push(Value::Bool(false))?,
+            Opcode::Pop => {
+                self.pop()?;
+            }
+            Opcode::GetLocal(slot) => {
+                let value = self.stack.get(slots + slot as usize)?;
+                self.push(value)?;
+            }
+            Opcode::SetLocal(slot) => {
+                let value = self.stack.peek(0)?.clone();
+                self.stack.set(slots + slot as usize, value)?;
+            }
+            Opcode::DefineGlobal(constant) => {
+                let name = global_name(chunk, constant)?;
+                let value = self.pop()?;
+                self.globals.insert(name.to_owned(), value);
+            }
+            Opcode::GetGlobal(constant) => {
+                let name = global_name(chunk, constant)?;
+                match self.globals.get(name) {
+                    Some(value) => self.push(value.clone())?,
+                    None => return Err(undefined_variable(name)),
                 }
-                Opcode::Negate => {
-                    let value = self.pop();
-                    self.push(-value);
+            }
+            Opcode::SetGlobal(constant) => {
+                let name = global_name(chunk, constant)?;
+                let value = self.stack.peek(0)?.clone();
+                match self.globals.get_mut(name) {
+                    Some(slot) => *slot = value,
+                    None => return Err(undefined_variable(name)),
                 }
-                Opcode::Not => {
-                    let value = self.pop();
-                    self.push(!value);
+            }
+            Opcode::GetProperty(constant) => {
+                let name = global_name(chunk, constant)?;
+                let value = match self.pop()? {
+                    Value::UserData(object) => object.get().get(self, name)?,
+                    other => return Err(not_an_object(&other)),
+                };
+                self.push(value)?;
+            }
+            Opcode::SetProperty(constant) => {
+                let name = global_name(chunk, constant)?;
+                let value = self.pop()?;
+                match self.pop()? {
+                    Value::UserData(object) => object.get().set(self, name, value.clone())?,
+                    other => return Err(not_an_object(&other)),
                 }
-                Opcode::Return => {
-                    if !self.stack.is_empty() {
-                        println!("{}", self.pop());
+                self.push(value)?;
+            }
+            Opcode::Equal => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push(Value::Bool(l == r))?;
+            }
+            Opcode::Greater => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push(Value::Bool(l > r))?;
+            }
+            Opcode::Less => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push(Value::Bool(l < r))?;
+            }
+            Opcode::Add => {
+                let val = match (self.pop()?, self.pop()?) {
+                    (Value::String(r), Value::String(l)) => {
+                        Value::String(self.heap.concat(l.get(), r.get())?)
                     }
-                    return Ok(());
+                    (r, l) => (l + r)?,
+                };
+                self.push(val)?;
+            }
+            Opcode::Concat(parts) => {
+                let parts = self.stack.top(parts as usize)?;
+                let joined = self.heap.join(parts)?;
+
+                self.stack.truncate(self.stack.len() - parts.len());
+                self.push(Value::String(joined))?;
+            }
+            Opcode::Subtract => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push((l - r)?)?;
+            }
+            Opcode::Multiply => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push((l * r)?)?;
+            }
+            Opcode::Divide => {
+                let (r, l) = (self.pop()?, self.pop()?);
+                self.push((l / r)?)?;
+            }
+            Opcode::Negate => {
+                let value = self.pop()?;
+                self.push((-value)?)?;
+            }
+            Opcode::Not => {
+                let value = self.pop()?;
+                self.push((!value)?)?;
+            }
+            Opcode::Call(argc) => {
+                let callee = self.stack.peek(argc as usize)?.clone();
+                self.call_value(callee, argc)?;
+            }
+            Opcode::Invoke(constant, argc) => {
+                let name = global_name(chunk, constant)?;
+                self.invoke(name, argc)?;
+            }
+            Opcode::Return => {
+                let result = self.pop()?;
+                let frame = self.frames.pop().expect("No active call frame");
+                self.stack.truncate(frame.slots);
+
+                if self.frames.len() == depth {
+                    return Ok(Some(result));
                 }
+                self.push(result)?;
             }
         }
+
+        Ok(None)
     }
 
     // error
-    fn runtime_error(&mut self, s: String) {
-        println!("{s}");
+    /// Attaches a stack trace to runtime errors that do not have one yet.
+    fn traced(&self, e: RxError) -> RxError {
+        match e {
+            RxError::Runtime(e) if e.trace().is_empty() => {
+                let trace = self.frames.iter().rev().map(CallFrame::to_frame).collect();
+                RxError::new(e.with_trace(trace))
+            }
+            e => e,
+        }
+    }
+}
+
+/// Name operand of an instruction; only hand-built chunks can refer to a
+/// missing or non-string constant.
+fn global_name(chunk: &Chunk, constant: u8) -> Res<&str> {
+    chunk
+        .constants
+        .get(constant as usize)
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            RxError::new(Runtime::new(
+                ErrorKind::InvalidBytecode,
+                &format!("Constant {constant} is not a name"),
+            ))
+        })
+}
 
-        let instruction = self
-            .ip
-            .checked_sub(self.chunk.code.len().checked_sub(1).unwrap_or_default())
-            .unwrap_or_default();
-        let line = self.chunk.lines[instruction];
+fn undefined_variable(name: &str) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::NameError,
+        &format!("Undefined variable '{name}'."),
+    ))
+}
 
-        println!("[line {}] in script", line);
+fn not_an_object(value: &Value) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::TypeError,
+        &format!("Only host objects have properties, not {}.", value.get_ty()),
+    ))
+}
 
-        self.stack.clear();
+fn arity_error(name: &str, arity: u8, argc: u8) -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::ArityError,
+        &format!("{name} expected {arity} arguments but got {argc}."),
+    ))
+}
+
+/// Whether `source` ends inside a block, a parenthesis, a string or a
+/// comment, so the REPL should read another line before compiling it.
+fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0i32;
+    for token in tokenize(source) {
+        let text = token.text(source);
+        match token.kind {
+            TokenType::OpenBrace | TokenType::OpenParen => depth += 1,
+            TokenType::CloseBrace | TokenType::CloseParen => depth -= 1,
+            // `"..${` opens an interpolation, `}.."` closes it
+            TokenType::Interpolation(_) if !text.starts_with('}') => depth += 1,
+            TokenType::String(_) if text.starts_with('}') => depth -= 1,
+            TokenType::Error(msg) if msg.starts_with("Unterminated") => return true,
+            _ => (),
+        }
     }
+    depth > 0
 }
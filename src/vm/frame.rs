@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use crate::{
+    error::Frame,
+    object::{Function, ObjRef},
+};
+
+/// What kind of code a call frame is executing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// Top level code of a script or REPL line.
+    Script,
+    /// A roxy function.
+    Function,
+    /// Host code called from roxy, or calling back into it.
+    Native,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+    pub kind: FrameKind,
+    /// `None` for native frames, which have no bytecode.
+    pub function: Option<ObjRef<Function>>,
+    pub name: Arc<str>,
+    pub file: Arc<str>,
+    pub ip: usize,
+    /// Stack index of the frame's first slot, which holds the callee.
+    pub slots: usize,
+}
+
+impl CallFrame {
+    pub fn new(kind: FrameKind, function: ObjRef<Function>, slots: usize) -> Self {
+        let f = function.get();
+        Self {
+            kind,
+            name: f.name.clone(),
+            file: f.file.clone(),
+            function: Some(function),
+            ip: 0,
+            slots,
+        }
+    }
+
+    pub fn native(name: Arc<str>, slots: usize) -> Self {
+        Self {
+            kind: FrameKind::Native,
+            function: None,
+            name,
+            file: "<native>".into(),
+            ip: 0,
+            slots,
+        }
+    }
+
+    /// Offset of the instruction currently being executed.
+    pub fn current_instruction(&self) -> usize {
+        // ip has already moved past it
+        self.ip.saturating_sub(1)
+    }
+
+    /// Source line of the instruction currently being executed.
+    pub fn line(&self) -> Option<usize> {
+        self.function
+            .as_ref()
+            .map(|f| f.get().chunk.line_for(self.current_instruction()))
+    }
+
+    pub fn to_frame(&self) -> Frame {
+        Frame {
+            name: self.name.to_string(),
+            file: self.file.to_string(),
+            line: self.line(),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use super::forward_sigint;
+
+/// Thread-safe token used to abort a running `Vm` from the outside.
+///
+/// Clones share the same flag, so a host can keep one on another thread and
+/// call `trigger` while the VM is executing. The VM checks the flag between
+/// instructions and unwinds with an `Interrupted` runtime error.
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt(Arc<AtomicBool>);
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trigger(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        forward_sigint(self);
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clears the flag, along with a Ctrl-C not yet picked up.
+    pub fn reset(&self) {
+        forward_sigint(self);
+        self.0.store(false, Ordering::SeqCst);
+    }
+
+    pub(crate) fn flag(&self) -> &Arc<AtomicBool> {
+        &self.0
+    }
+}
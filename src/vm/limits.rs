@@ -0,0 +1,31 @@
+/// Resource limits enforced by a `Vm`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of values on the value stack.
+    pub max_stack: usize,
+    /// Maximum number of nested call frames.
+    pub max_frames: usize,
+    /// Instructions a single run may execute, unlimited when `None`.
+    pub max_instructions: Option<u64>,
+    /// Bytes the heap may hold, unlimited when `None`.
+    pub max_heap: Option<usize>,
+    /// Longest string in bytes a script may create, unlimited when `None`.
+    pub max_string_len: Option<usize>,
+}
+
+impl Limits {
+    pub const STACK_SIZE: usize = u8::MAX as usize + 1;
+    pub const FRAMES_MAX: usize = 64;
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_stack: Self::STACK_SIZE,
+            max_frames: Self::FRAMES_MAX,
+            max_instructions: None,
+            max_heap: None,
+            max_string_len: None,
+        }
+    }
+}
@@ -1,3 +1,17 @@
+mod buffer;
+mod builder;
+mod editor;
 mod engine;
+mod frame;
+mod interrupt;
+mod limits;
+mod natives;
+mod signal;
+mod stack;
+mod terminal;
+mod trace;
 
-pub use self::engine::*;
+pub use self::{
+    buffer::*, builder::*, editor::*, engine::*, frame::*, interrupt::*, limits::*, signal::*,
+    stack::*, terminal::*, trace::*,
+};
@@ -0,0 +1,20 @@
+//! Native functions available to every script.
+
+use crate::{value::Value, Res};
+
+use super::Vm;
+
+pub(super) fn register(vm: &mut Vm) {
+    // a fresh heap has no limits small enough to reject these
+    let _ = vm.register_native("print", None, print);
+}
+
+fn print(vm: &mut Vm, args: &[Value]) -> Res<Value> {
+    let line = args
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    writeln!(vm.stdout(), "{line}")?;
+    Ok(Value::Nil)
+}
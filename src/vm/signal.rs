@@ -0,0 +1,115 @@
+//! Routes Ctrl-C to an `Interrupt` while the REPL is running.
+//!
+//! The handler only records the signal in a static flag; the interrupt
+//! catching it picks the flag up the next time it is checked. Nothing the
+//! handler touches is ever freed.
+
+use super::Interrupt;
+
+/// Restores the previous SIGINT handler and target when dropped.
+pub struct SigintGuard {
+    #[cfg(unix)]
+    previous: imp::Previous,
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        ffi::c_int,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::{Interrupt, SigintGuard};
+
+    const SIGINT: c_int = 2;
+    const SIG_ERR: usize = usize::MAX;
+
+    /// Set by the handler, cleared when forwarded to the target.
+    static PENDING: AtomicBool = AtomicBool::new(false);
+    /// Identity of the flag of the `Interrupt` catching SIGINT, 0 for none.
+    /// Only ever compared, never dereferenced.
+    static TARGET: AtomicUsize = AtomicUsize::new(0);
+
+    pub struct Previous {
+        handler: usize,
+        target: usize,
+    }
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    extern "C" fn on_sigint(_: c_int) {
+        PENDING.store(true, Ordering::SeqCst);
+    }
+
+    fn id(interrupt: &Interrupt) -> usize {
+        Arc::as_ptr(interrupt.flag()) as usize
+    }
+
+    pub fn install(interrupt: &Interrupt) -> SigintGuard {
+        let target = TARGET.swap(id(interrupt), Ordering::SeqCst);
+
+        let handler: extern "C" fn(c_int) = on_sigint;
+        // SAFETY: the handler only performs an atomic store
+        let handler = unsafe { signal(SIGINT, handler as usize) };
+
+        SigintGuard {
+            previous: Previous { handler, target },
+        }
+    }
+
+    pub fn uninstall(guard: &SigintGuard) {
+        let previous = &guard.previous;
+        if previous.handler != SIG_ERR {
+            // SAFETY: the handler was returned by signal() for SIGINT
+            unsafe { signal(SIGINT, previous.handler) };
+        }
+        TARGET.store(previous.target, Ordering::SeqCst);
+    }
+
+    pub fn forward(interrupt: &Interrupt) {
+        if PENDING.load(Ordering::Relaxed)
+            && TARGET.load(Ordering::SeqCst) == id(interrupt)
+            && PENDING.swap(false, Ordering::SeqCst)
+        {
+            interrupt.trigger();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::{Interrupt, SigintGuard};
+
+    pub fn install(_: &Interrupt) -> SigintGuard {
+        SigintGuard {}
+    }
+
+    pub fn uninstall(_: &SigintGuard) {}
+
+    pub fn forward(_: &Interrupt) {}
+}
+
+/// Makes Ctrl-C trigger `interrupt` instead of terminating the process,
+/// until the guard is dropped.
+///
+/// Guards nest: dropping one restores the handler and interrupt that were
+/// catching SIGINT before it, including a handler the host installed.
+pub fn catch_sigint(interrupt: &Interrupt) -> SigintGuard {
+    imp::install(interrupt)
+}
+
+/// Triggers `interrupt` if it is catching SIGINT and one arrived.
+pub(super) fn forward_sigint(interrupt: &Interrupt) {
+    imp::forward(interrupt);
+}
+
+impl Drop for SigintGuard {
+    fn drop(&mut self) {
+        imp::uninstall(self);
+    }
+}
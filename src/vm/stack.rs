@@ -0,0 +1,98 @@
+use crate::{
+    error::{ErrorKind, Runtime, RxError},
+    value::Value,
+    Res,
+};
+
+/// Bounds-checked value stack.
+///
+/// The backing storage is allocated once for the maximum depth and never
+/// grows past it; exceeding it raises a `StackOverflow` runtime error.
+#[derive(Debug)]
+pub struct Stack {
+    values: Vec<Value>,
+    max: usize,
+}
+
+impl Stack {
+    pub fn new(max: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(max),
+            max,
+        }
+    }
+
+    pub fn push(&mut self, value: Value) -> Res<()> {
+        if self.values.len() >= self.max {
+            return Err(RxError::new(Runtime::new(
+                ErrorKind::StackOverflow,
+                &format!("Value stack exceeded {} slots", self.max),
+            )));
+        }
+
+        self.values.push(value);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Res<Value> {
+        self.values.pop().ok_or_else(underflow)
+    }
+
+    pub fn peek(&self, distance: usize) -> Res<&Value> {
+        self.values
+            .len()
+            .checked_sub(distance + 1)
+            .map(|idx| &self.values[idx])
+            .ok_or_else(underflow)
+    }
+
+    /// The topmost `count` values, oldest first.
+    pub fn top(&self, count: usize) -> Res<&[Value]> {
+        self.values
+            .len()
+            .checked_sub(count)
+            .map(|start| &self.values[start..])
+            .ok_or_else(underflow)
+    }
+
+    pub fn get(&self, idx: usize) -> Res<Value> {
+        self.values.get(idx).cloned().ok_or_else(underflow)
+    }
+
+    pub fn set(&mut self, idx: usize, value: Value) -> Res<()> {
+        let slot = self.values.get_mut(idx).ok_or_else(underflow)?;
+        *slot = value;
+        Ok(())
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.values.truncate(len);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn as_slice(&self) -> &[Value] {
+        &self.values
+    }
+}
+
+fn underflow() -> RxError {
+    RxError::new(Runtime::new(
+        ErrorKind::StackUnderflow,
+        "Pop from an empty value stack",
+    ))
+}
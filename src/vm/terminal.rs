@@ -0,0 +1,118 @@
+//! Raw mode and width of the terminal the REPL reads from.
+
+use std::io;
+
+/// Keeps stdin in raw mode, without echo or line buffering, until dropped.
+pub struct RawMode {
+    #[cfg(unix)]
+    saved: imp::Termios,
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        ffi::{c_int, c_ulong},
+        io,
+    };
+
+    use super::RawMode;
+
+    const STDIN: c_int = 0;
+    const STDOUT: c_int = 1;
+    const TCSADRAIN: c_int = 1;
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+
+    /// Opaque `struct termios`, large enough for every platform's layout;
+    /// only `cfmakeraw` touches its fields.
+    #[repr(C, align(8))]
+    #[derive(Clone, Copy)]
+    pub struct Termios([u8; 256]);
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Winsize {
+        rows: u16,
+        cols: u16,
+        x_pixels: u16,
+        y_pixels: u16,
+    }
+
+    extern "C" {
+        fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+        fn cfmakeraw(termios: *mut Termios);
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    pub fn enable() -> io::Result<RawMode> {
+        let mut saved = Termios([0; 256]);
+        // SAFETY: saved is a writable buffer larger than struct termios
+        if unsafe { tcgetattr(STDIN, &mut saved) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut raw = saved;
+        // SAFETY: raw holds the attributes tcgetattr filled in
+        unsafe { cfmakeraw(&mut raw) };
+        set(&raw)?;
+        Ok(RawMode { saved })
+    }
+
+    pub fn disable(mode: &RawMode) {
+        let _ = set(&mode.saved);
+    }
+
+    fn set(termios: &Termios) -> io::Result<()> {
+        // SAFETY: termios was produced by tcgetattr
+        match unsafe { tcsetattr(STDIN, TCSADRAIN, termios) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn width() -> Option<usize> {
+        let mut size = Winsize::default();
+        // SAFETY: TIOCGWINSZ writes a struct winsize
+        let ok = unsafe { ioctl(STDOUT, TIOCGWINSZ, &mut size as *mut Winsize) } == 0;
+        (ok && size.cols > 0).then_some(size.cols as usize)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+
+    use super::RawMode;
+
+    pub fn enable() -> io::Result<RawMode> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn disable(_: &RawMode) {}
+
+    pub fn width() -> Option<usize> {
+        None
+    }
+}
+
+impl RawMode {
+    /// Switches stdin to raw mode, failing if it is not a terminal.
+    pub fn enable() -> io::Result<Self> {
+        imp::enable()
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        imp::disable(self);
+    }
+}
+
+/// Columns of the terminal on stdout, if it is one.
+pub fn terminal_width() -> Option<usize> {
+    imp::width()
+}
@@ -0,0 +1,160 @@
+use std::{
+    io::{self, Write},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    chunks::{write_op, write_stack, Chunk, Opcode},
+    value::Value,
+};
+
+/// How a `Tracer` writes each instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    /// Disassembler listing preceded by the stack, for reading.
+    #[default]
+    Text,
+    /// One JSON object per line with `fn`, `ip`, `line`, `opcode`,
+    /// `operands` and `stack`, for tools. Numbers, bools and nil are JSON
+    /// values on the stack, everything else its printed form as a string.
+    Json,
+}
+
+/// Records every instruction a `Vm` executes, before it runs.
+///
+/// ```
+/// use roxy::vm::{SharedBuffer, TraceFormat, Tracer, Vm};
+///
+/// let out = SharedBuffer::new();
+/// let tracer = Tracer::new(out.clone())
+///     .format(TraceFormat::Json)
+///     .opcodes(["Add"]);
+/// let mut vm = Vm::builder().tracer(tracer).build();
+/// vm.eval("1 + 2").unwrap();
+/// assert!(out.contents().starts_with(r#"{"fn":"script","ip":2,"#));
+/// ```
+pub struct Tracer {
+    out: Box<dyn Write + Send>,
+    format: TraceFormat,
+    lines: Vec<RangeInclusive<usize>>,
+    opcodes: Vec<String>,
+}
+
+impl Tracer {
+    pub fn new<W: Write + Send + 'static>(out: W) -> Self {
+        Self {
+            out: Box::new(out),
+            format: TraceFormat::default(),
+            lines: Vec::new(),
+            opcodes: Vec::new(),
+        }
+    }
+
+    pub fn format(mut self, format: TraceFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Only traces instructions compiled from these source lines.
+    pub fn lines<I: IntoIterator<Item = RangeInclusive<usize>>>(mut self, lines: I) -> Self {
+        self.lines.extend(lines);
+        self
+    }
+
+    /// Only traces these opcodes, named with or without the `OP_` prefix.
+    pub fn opcodes<I, S>(mut self, opcodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.opcodes.extend(opcodes.into_iter().map(Into::into));
+        self
+    }
+
+    pub(super) fn instruction(
+        &mut self,
+        function: &str,
+        chunk: &Chunk,
+        ip: usize,
+        op: &Opcode,
+        stack: &[Value],
+    ) -> io::Result<()> {
+        let line = chunk.line_for(ip);
+        if !self.wants(line, op) {
+            return Ok(());
+        }
+
+        match self.format {
+            TraceFormat::Text => {
+                write_stack(&mut self.out, stack)?;
+                write!(self.out, "{function}:{ip:04} {line:>4} ")?;
+                write_op(&mut self.out, chunk, op)
+            }
+            TraceFormat::Json => {
+                let out = &mut self.out;
+                write!(out, "{{\"fn\":")?;
+                write_json_string(out, function)?;
+                write!(
+                    out,
+                    ",\"ip\":{ip},\"line\":{line},\"opcode\":\"{}\"",
+                    op.name()
+                )?;
+
+                write!(out, ",\"operands\":[")?;
+                for (idx, operand) in op.operands().iter().enumerate() {
+                    if idx > 0 {
+                        write!(out, ",")?;
+                    }
+                    write!(out, "{operand}")?;
+                }
+
+                write!(out, "],\"stack\":[")?;
+                for (idx, value) in stack.iter().enumerate() {
+                    if idx > 0 {
+                        write!(out, ",")?;
+                    }
+                    write_json_value(out, value)?;
+                }
+                writeln!(out, "]}}")
+            }
+        }
+    }
+
+    fn wants(&self, line: usize, op: &Opcode) -> bool {
+        let line_ok = self.lines.is_empty() || self.lines.iter().any(|r| r.contains(&line));
+        let op_ok = self.opcodes.is_empty()
+            || self.opcodes.iter().any(|name| {
+                let name = name.strip_prefix("OP_").unwrap_or(name);
+                op.name()[3..].eq_ignore_ascii_case(name)
+            });
+        line_ok && op_ok
+    }
+}
+
+fn write_json_value(out: &mut dyn Write, value: &Value) -> io::Result<()> {
+    match value {
+        Value::Int(i) => write!(out, "{i}"),
+        // `Debug` keeps the fraction, so `1.0` stays apart from `1`
+        Value::Float(f) if f.is_finite() => write!(out, "{f:?}"),
+        Value::Bool(b) => write!(out, "{b}"),
+        Value::Nil => write!(out, "null"),
+        Value::String(s) => write_json_string(out, s.get()),
+        other => write_json_string(out, &other.to_string()),
+    }
+}
+
+fn write_json_string(out: &mut dyn Write, s: &str) -> io::Result<()> {
+    write!(out, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(out, "\\\"")?,
+            '\\' => write!(out, "\\\\")?,
+            '\n' => write!(out, "\\n")?,
+            '\r' => write!(out, "\\r")?,
+            '\t' => write!(out, "\\t")?,
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{c}")?,
+        }
+    }
+    write!(out, "\"")
+}
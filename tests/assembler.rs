@@ -0,0 +1,241 @@
+use roxy::{
+    chunks::{assemble, Chunk, Disassembler, Opcode},
+    compiler,
+    error::ErrorKind,
+    object::{Function, Heap, ObjRef},
+    value::Value,
+    vm::Vm,
+};
+
+fn listing(chunk: &Chunk) -> String {
+    let mut out = Vec::new();
+    Disassembler::new(chunk, None)
+        .disassemble(&mut out, "script")
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn assert_same_chunk(a: &Chunk, b: &Chunk) {
+    assert_eq!(a.code, b.code);
+    assert_eq!(a.lines, b.lines);
+    assert_eq!(a.constants.len(), b.constants.len());
+
+    for (a, b) in a.constants.iter().zip(&b.constants) {
+        match (a, b) {
+            (Value::Float(a), Value::Float(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+            (Value::Function(a), Value::Function(b)) => {
+                let (a, b) = (a.get(), b.get());
+                assert_eq!((&a.name, a.arity), (&b.name, b.arity));
+                assert_same_chunk(&a.chunk, &b.chunk);
+            }
+            (a, b) => {
+                assert_eq!(a.get_ty(), b.get_ty());
+                assert_eq!(a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn disassembly_round_trips() {
+    let sources = [
+        include_str!("../programs/numbers.rx"),
+        include_str!("../programs/print.rx"),
+        "var a = \"a\"; a; var b = 2.0; b = 1e-9 + -0.5; nil != true",
+        "fn outer(x, y) {\n  fn inner() { return \"q\\\"\\n\\u{1}\"; }\n  var z = x;\n  { var w = z; }\n  return inner();\n}\nouter(1, 2)",
+        "fn f() {} fn f() { return f; } \"${f} and ${1}\"",
+        "var o = nil; o.p = 1; o.m(1, 2); o.p",
+    ];
+
+    for source in sources {
+        let mut heap = Heap::new(None, None);
+        let script = compiler::compile(source, "<script>", &mut heap).unwrap();
+        let chunk = &script.get().chunk;
+
+        let text = listing(chunk);
+        let rebuilt = assemble(&text, "<script>", &mut heap).unwrap();
+        assert_same_chunk(chunk, &rebuilt.chunk);
+        assert_eq!(listing(&rebuilt.chunk), text);
+    }
+}
+
+#[test]
+fn unreferenced_constants_round_trip() {
+    let mut heap = Heap::new(None, None);
+    let mut chunk = Chunk::new();
+    chunk.add_constant(Value::Nil).unwrap();
+    chunk.add_constant(2.5).unwrap();
+    chunk.write(Opcode::Constant(1), 3);
+    chunk.write(Opcode::Return, 3);
+
+    let text = listing(&chunk);
+    assert!(text.contains(".const 0 nil\n"), "{text}");
+    let rebuilt = assemble(&text, "<asm>", &mut heap).unwrap();
+    assert_same_chunk(&chunk, &rebuilt.chunk);
+}
+
+#[test]
+fn hand_written_assembly_runs() {
+    let source = r#"
+        ; (1.5 + 2) * 2, through a global
+        .line 1
+        OP_Constant 1.5
+        OP_Constant 2
+        OP_Add
+        OP_DefineGlobal "x"
+    loop:
+        .line 2
+        OP_GetGlobal "x"
+        OP_Constant 0 1.5     ; reuses constant 0
+        OP_Constant 2.0
+        OP_Multiply
+        OP_Add
+        OP_Return
+    "#;
+
+    let mut vm = Vm::new();
+    assert_eq!(vm.eval_asm("<asm>", source).unwrap().as_float(), Some(6.5));
+}
+
+#[test]
+fn functions_come_from_later_sections() {
+    let source = "
+== BEGIN script ==
+OP_Constant <fn twice>
+OP_Constant 21
+OP_Call 1
+OP_Return
+== END   script ==
+
+== BEGIN twice ==
+.arity 1
+OP_GetLocal 1
+OP_Constant 2
+OP_Multiply
+OP_Return
+== END   twice ==
+";
+    let mut vm = Vm::new();
+    assert_eq!(vm.eval_asm("<asm>", source).unwrap().as_int(), Some(42));
+}
+
+#[test]
+fn errors_point_at_the_word() {
+    let cases = [
+        ("OP_Add\nOP_Jump 3", "'OP_Jump'", 2, "Unknown instruction."),
+        (
+            "OP_Constant",
+            "'OP_Constant'",
+            1,
+            "Wrong number of operands.",
+        ),
+        (
+            "OP_Constant 0 1\nOP_Constant 0 2",
+            "'0'",
+            2,
+            "Constant is already defined as another value.",
+        ),
+        ("OP_Constant \"open", "'\"open'", 1, "Unterminated string."),
+        ("a:\na:", "'a:'", 2, "Label is already defined."),
+        (
+            "OP_Constant <fn f>\nOP_Return",
+            "'script'",
+            1,
+            "Missing the section of function 'f'.",
+        ),
+        (
+            "OP_Constant 2 nil",
+            "'script'",
+            1,
+            "Constant 0 is never defined.",
+        ),
+    ];
+
+    for (source, lexeme, line, msg) in cases {
+        let err = assemble(source, "<asm>", &mut Heap::new(None, None)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError);
+        assert_eq!(err.span().map(|span| span.2), Some(line), "{err}");
+        assert!(err.to_string().contains(lexeme), "{err}");
+        assert!(err.to_string().ends_with(msg), "{err}");
+    }
+}
+
+#[test]
+fn concat_past_the_stack_underflows() {
+    let mut vm = Vm::new();
+    let err = vm
+        .eval_asm("<asm>", "OP_Constant \"a\"\nOP_Concat 5\nOP_Return")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::StackUnderflow);
+}
+
+#[test]
+fn sections_must_end_with_return() {
+    let err = assemble("OP_Nil", "<asm>", &mut Heap::new(None, None)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert!(err
+        .to_string()
+        .ends_with("Expect the section to end with OP_Return."));
+
+    let source = "OP_Constant <fn f>\nOP_Return\n== BEGIN f ==\nOP_Nil\n== END   f ==";
+    let err = assemble(source, "<asm>", &mut Heap::new(None, None)).unwrap_err();
+    assert!(err.to_string().contains("'f'"), "{err}");
+}
+
+#[test]
+fn name_operands_must_be_strings() {
+    for source in [
+        "OP_GetGlobal 1\nOP_Return",
+        "OP_Nil\nOP_DefineGlobal 0 1\nOP_Return",
+    ] {
+        let err = assemble(source, "<asm>", &mut Heap::new(None, None)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError);
+        assert!(err
+            .to_string()
+            .ends_with("at '1': Expect a string constant as the name."));
+    }
+
+    let err = Vm::new()
+        .eval_asm("g.rxasm", "OP_GetGlobal 1\nOP_Return")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+}
+
+#[test]
+fn chunks_without_return_fail_at_runtime() {
+    let mut function = Function::new("script".into(), "<host>".into());
+    function.chunk.write(Opcode::Nil, 1);
+
+    let mut vm = Vm::new();
+    let err = vm
+        .call(Value::Function(ObjRef::new(function)), &[])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidBytecode);
+}
+
+#[test]
+fn non_string_names_fail_at_runtime() {
+    let mut function = Function::new("script".into(), "<host>".into());
+    let constant = function.chunk.add_constant(1).unwrap();
+    function.chunk.write(Opcode::GetGlobal(constant), 1);
+    function.chunk.write(Opcode::Return, 1);
+
+    let mut vm = Vm::new();
+    let err = vm
+        .call(Value::Function(ObjRef::new(function)), &[])
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidBytecode);
+    assert!(
+        err.to_string().contains("Constant 0 is not a name"),
+        "{err}"
+    );
+}
+
+#[test]
+fn runtime_errors_name_the_file() {
+    let mut vm = Vm::new();
+    let err = vm
+        .eval_asm("foo.rxasm", "OP_Nil\nOP_Negate\nOP_Return")
+        .unwrap_err();
+    assert!(err.to_string().contains("(foo.rxasm)"), "{err}");
+}
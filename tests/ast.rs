@@ -0,0 +1,93 @@
+use roxy::{
+    compiler::{
+        ast::{BinaryOp, ExprKind, Literal, StmtKind},
+        Parser,
+    },
+    error::{ErrorKind, RxError},
+    vm::Vm,
+};
+
+#[test]
+fn parses_statements_and_result() {
+    let source = "var x = 1;\nfn add(a, b) { return a + b; }\nadd(x, 2.5)";
+    let script = Parser::new(source).parse().unwrap();
+
+    assert_eq!(script.body.len(), 2);
+    let StmtKind::Var { name, init } = &script.body[0].kind else {
+        panic!("expected a var declaration");
+    };
+    assert_eq!(name.name, "x");
+    assert!(matches!(
+        init.as_ref().map(|init| &init.kind),
+        Some(ExprKind::Literal(Literal::Int(1)))
+    ));
+
+    let StmtKind::Fn(decl) = &script.body[1].kind else {
+        panic!("expected a function declaration");
+    };
+    let params = decl
+        .params
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(params, ["a", "b"]);
+    let stmt = &script.body[1];
+    assert_eq!(
+        &source[stmt.span.0..stmt.span.1],
+        "fn add(a, b) { return a + b; }"
+    );
+    assert_eq!(stmt.span.2, 2);
+
+    let result = script.result.unwrap();
+    let ExprKind::Call { args, .. } = &result.kind else {
+        panic!("expected a call");
+    };
+    assert_eq!(args.len(), 2);
+    assert_eq!(&source[result.span.0..result.span.1], "add(x, 2.5)");
+    assert_eq!(result.span.2, 3);
+}
+
+#[test]
+fn binary_precedence() {
+    let script = Parser::new("1 + 2 * 3").parse().unwrap();
+    let ExprKind::Binary { op, rhs, .. } = script.result.unwrap().kind else {
+        panic!("expected a binary expression");
+    };
+    assert_eq!(op, BinaryOp::Add);
+    assert!(matches!(
+        rhs.kind,
+        ExprKind::Binary {
+            op: BinaryOp::Multiply,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn scope_errors_come_from_codegen() {
+    let cases = [
+        ("return 1;", "'return'", "Can't return from top-level code."),
+        (
+            "{ var a = 1; var a = 2; }",
+            "'a'",
+            "Already a variable with this name in this scope.",
+        ),
+        (
+            "{ var a = a; }",
+            "'a'",
+            "Can't read local variable in its own initializer.",
+        ),
+    ];
+
+    for (source, lexeme, msg) in cases {
+        assert!(Parser::new(source).parse().is_ok(), "{source}");
+
+        let err = Vm::new().eval(source).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError);
+        let RxError::Compile(err) = err else {
+            panic!("expected a compile error");
+        };
+        assert_eq!(err.message(), msg);
+        assert!(err.to_string().contains(lexeme), "{err}");
+    }
+}
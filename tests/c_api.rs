@@ -0,0 +1,61 @@
+use std::{env, fs, path::PathBuf, process::Command};
+
+const ROOT: &str = env!("CARGO_MANIFEST_DIR");
+
+/// Directory holding the libroxy cdylib, next to the test's `deps` dir.
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn header_is_generated_from_ffi() {
+    let header = fs::read_to_string(format!("{ROOT}/include/roxy.h")).unwrap();
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/roxy.h"));
+    assert_eq!(header, generated, "include/roxy.h is stale, rebuild");
+
+    let source = fs::read_to_string(format!("{ROOT}/src/ffi.rs")).unwrap();
+    let exports = source
+        .lines()
+        .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+        .filter_map(|rest| rest.split('(').next())
+        .collect::<Vec<_>>();
+
+    assert!(!exports.is_empty());
+    for name in exports {
+        assert!(header.contains(&format!("{name}(")), "{name} missing");
+    }
+    assert!(header.contains("int roxy_eval(RoxyVm *vm, const char *source, RoxyValue *out);"));
+}
+
+#[test]
+fn c_program_runs_a_script() {
+    if Command::new("cc").arg("--version").output().is_err() {
+        eprintln!("skipping: no C compiler");
+        return;
+    }
+
+    let lib = library_dir();
+    let exe = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("numbers");
+    let status = Command::new("cc")
+        .arg(format!("-I{ROOT}/include"))
+        .arg(format!("{ROOT}/examples/c/numbers.c"))
+        .arg(format!("-L{}", lib.display()))
+        .arg("-lroxy")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&exe)
+        .arg(format!("{ROOT}/programs/numbers.rx"))
+        .env("LD_LIBRARY_PATH", &lib)
+        .env("DYLD_LIBRARY_PATH", &lib)
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "12.0121\n42\n");
+}
@@ -0,0 +1,114 @@
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn roxy(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .args(args)
+        .env_remove("ROXY_TRACE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn evaluates_one_liners() {
+    let output = roxy(&["-e", "1 + 2"], "");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), "3\n");
+
+    let output = roxy(&["-e", "print(args)", "a", "--b"], "");
+    assert_eq!(stdout(&output), "[a, --b]\n");
+}
+
+#[test]
+fn runs_stdin_with_arguments() {
+    let output = roxy(&["run", "-", "x", "-e"], "print(args);");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), "[x, -e]\n");
+}
+
+#[test]
+fn exit_codes_follow_the_failure() {
+    let cases: [(&[&str], i32); 6] = [
+        (&["--nope"], 64),
+        (&["run"], 64),
+        (&["-e", "1 +"], 65),
+        (&["-e", "nil + 1"], 70),
+        (&["missing.rx"], 74),
+        (&["check", "programs/print.rx"], 0),
+    ];
+
+    for (args, code) in cases {
+        let output = roxy(args, "");
+        assert_eq!(output.status.code(), Some(code), "{args:?}: {output:?}");
+    }
+}
+
+#[test]
+fn prints_version_and_usage() {
+    let output = roxy(&["--version"], "");
+    assert_eq!(
+        stdout(&output),
+        format!("roxy {}\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    let output = roxy(&["--help"], "");
+    assert!(stdout(&output).starts_with("Usage: roxy"));
+    for command in ["run", "repl", "check", "compile", "dis", "fmt", "test"] {
+        assert!(stdout(&output).contains(&format!("\n    {command} ")));
+    }
+}
+
+#[test]
+fn compiles_to_a_runnable_listing() {
+    let output = roxy(&["compile", "-", "-o", "-"], "print(\"hi\");");
+    assert!(output.status.success(), "{output:?}");
+    let listing = stdout(&output);
+    assert!(listing.starts_with("== BEGIN script =="));
+
+    let dir = std::env::temp_dir().join(format!("roxy-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("hi.rxasm");
+    std::fs::write(&file, listing).unwrap();
+
+    let output = roxy(&[file.to_str().unwrap()], "");
+    assert_eq!(stdout(&output), "hi\n");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn tests_compare_expectations() {
+    let dir = std::env::temp_dir().join(format!("roxy-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("pass.rx"), "print(1 + 1); // expect: 2\n").unwrap();
+    std::fs::write(
+        dir.join("error.rx"),
+        "nil + 1; // expect error: TypeError\n",
+    )
+    .unwrap();
+
+    let output = roxy(&["test", dir.to_str().unwrap()], "");
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).ends_with("2 passed, 0 failed\n"));
+
+    std::fs::write(dir.join("fail.rx"), "print(1); // expect: 2\n").unwrap();
+    let output = roxy(&["test", dir.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("FAIL "));
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,54 @@
+use roxy::{
+    compiler::{Cursor, TokenType},
+    error::ErrorKind,
+    vm::Vm,
+};
+
+#[test]
+fn comments_are_skipped() {
+    let mut vm = Vm::new();
+    let value = vm
+        .eval(
+            "// leading comment
+            var a = 4; // trailing comment
+            /* block /* nested */ still comment */
+            a / 2 /* inline */",
+        )
+        .unwrap();
+    assert_eq!(value.as_int(), Some(2));
+}
+
+#[test]
+fn block_comments_count_lines() {
+    let mut vm = Vm::new();
+    let err = vm.eval("/*\n\n*/\n-nil;").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    let roxy::error::RxError::Runtime(err) = err else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.line(), 4);
+}
+
+#[test]
+fn unterminated_block_comment() {
+    let mut vm = Vm::new();
+    let err = vm.eval("1 /* /* */").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert!(err.to_string().contains("Unterminated block comment."));
+}
+
+#[test]
+fn doc_comments_are_trivia_tokens() {
+    let mut cursor = Cursor::new("/// Adds one.\n//// not docs\nfn inc(x) { return x + 1; }");
+
+    let token = cursor.advance();
+    assert!(matches!(token.kind, TokenType::DocComment(" Adds one.")));
+    assert_eq!(token.line(), 1);
+
+    let token = cursor.advance();
+    assert!(matches!(token.kind, TokenType::Comment("//// not docs")));
+
+    let token = cursor.advance();
+    assert_eq!(token.kind, TokenType::Fn);
+    assert_eq!(token.line(), 3);
+}
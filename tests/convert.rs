@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use roxy::{
+    convert::{FromValue, IntoValue},
+    error::{ErrorKind, RxError},
+    value::Value,
+    vm::Vm,
+};
+
+#[test]
+fn rust_values_round_trip() {
+    let mut vm = Vm::new();
+
+    let value = 200u8.into_value(&mut vm).unwrap();
+    assert_eq!(u8::from_value(value).unwrap(), 200);
+
+    let value = "hello".into_value(&mut vm).unwrap();
+    assert_eq!(String::from_value(value).unwrap(), "hello");
+
+    let value = vec![Some(1i32), None].into_value(&mut vm).unwrap();
+    assert_eq!(value.to_string(), "[1, nil]");
+    assert_eq!(
+        Vec::<Option<i32>>::from_value(value).unwrap(),
+        vec![Some(1), None]
+    );
+
+    let map = HashMap::from([("b".to_owned(), 2.5), ("a".to_owned(), 1.0)]);
+    let value = map.clone().into_value(&mut vm).unwrap();
+    assert_eq!(value.to_string(), "{a: 1, b: 2.5}");
+    assert_eq!(HashMap::<String, f64>::from_value(value).unwrap(), map);
+
+    let value = (1i64, true, "x").into_value(&mut vm).unwrap();
+    let (a, b, c) = <(i64, bool, String)>::from_value(value).unwrap();
+    assert_eq!((a, b, c.as_str()), (1, true, "x"));
+}
+
+#[test]
+fn conversion_errors_name_both_types() {
+    let mut vm = Vm::new();
+
+    let value = "1".into_value(&mut vm).unwrap();
+    let err = i64::from_value(value).unwrap_err();
+    assert!(matches!(err, RxError::Runtime(_)));
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(err.to_string().contains("Expected int64 but got string."));
+
+    let err = f64::try_from(Value::Nil).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+
+    let err = u8::from_value(Value::from(300)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ArithmeticError);
+}
+
+#[test]
+fn typed_natives() {
+    let mut vm = Vm::new();
+    vm.register_fn("add", |a: i64, b: i64| a + b).unwrap();
+    vm.register_fn("greet", |name: String| format!("Hello {name}"))
+        .unwrap();
+    vm.register_fn("half", |x: f64| if x < 0.0 { None } else { Some(x / 2.0) })
+        .unwrap();
+
+    assert_eq!(vm.eval("add(2, 3)").unwrap().as_int(), Some(5));
+    assert_eq!(
+        vm.eval("greet(\"roxy\")").unwrap().as_str(),
+        Some("Hello roxy")
+    );
+    assert_eq!(vm.eval("half(3)").unwrap().as_float(), Some(1.5));
+    assert!(vm.eval("half(-1)").unwrap().is_nil());
+
+    let err = vm.eval("add(1, \"2\")").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+
+    let err = vm.eval("add(1)").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ArityError);
+}
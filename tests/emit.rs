@@ -0,0 +1,61 @@
+use std::process::Command;
+
+fn emit(mode: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .arg(format!("--emit={mode}"))
+        .arg("programs/print.rx")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn emits_tokens() {
+    let tokens = emit("tokens");
+    assert!(tokens.starts_with("   1 Ident(\"print\")\n   1 OpenParen\n"));
+    assert!(tokens.ends_with("   1 SemiColon\n"));
+}
+
+#[test]
+fn emits_ast() {
+    let ast = emit("ast");
+    assert!(ast.starts_with("   1 Expr\n   1   Call\n   1     Variable print\n"));
+    assert!(ast.contains("   1           Binary -\n"));
+}
+
+#[test]
+fn emits_bytecode_without_running() {
+    let bytecode = emit("bytecode");
+    assert!(bytecode.starts_with("== BEGIN script ==\n0000    1 OP_GetGlobal        0 \"print\"\n"));
+    assert!(bytecode.contains("OP_Call             1\n"));
+    // print would have written the result
+    assert!(!bytecode.contains("-1"));
+}
+
+#[test]
+fn unknown_mode_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .args(["--emit=llvm", "programs/print.rx"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage:"));
+}
+
+#[test]
+fn emit_with_another_command_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .args(["run", "--emit=ast", "programs/print.rx"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(64));
+    assert!(output.stdout.is_empty(), "the script must not run");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--emit only works with compile."));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .args(["compile", "--emit=ast", "-o", "-", "programs/print.rx"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+}
@@ -0,0 +1,71 @@
+use roxy::{
+    value::Value,
+    vm::{SharedBuffer, Vm},
+};
+
+#[test]
+fn eval_returns_the_result() {
+    let mut vm = Vm::new();
+
+    assert_eq!(vm.eval("1 + 2 * 3").unwrap().as_int(), Some(7));
+    assert_eq!(vm.eval("1 < 2").unwrap().as_bool(), Some(true));
+    assert_eq!(vm.eval("7 / 2.0").unwrap().as_float(), Some(3.5));
+    assert!(vm.eval("nil").unwrap().is_nil());
+}
+
+#[test]
+fn eval_returns_strings() {
+    let mut vm = Vm::new();
+
+    let value = vm.eval("\"roxy\" + \" \" + \"vm\"").unwrap();
+    assert_eq!(value.as_str(), Some("roxy vm"));
+    assert_eq!(value.as_int(), None);
+    assert_eq!(Value::from(2), Value::Int(2));
+}
+
+#[test]
+fn repl_uses_configured_streams() {
+    let out = SharedBuffer::new();
+    let err = SharedBuffer::new();
+    let mut vm = Vm::builder()
+        .stdout(out.clone())
+        .stderr(err.clone())
+        .stdin("1 + 2\n-nil\nq\n".as_bytes())
+        .build();
+
+    vm.run_repl().unwrap();
+
+    assert_eq!(out.contents(), "roxy:> 3\nroxy:> roxy:> Exiting...\n");
+    assert!(err.contents().contains("Unable to negate nil"));
+}
+
+#[test]
+fn repl_continues_open_blocks_and_strings() {
+    let out = SharedBuffer::new();
+    let mut vm = Vm::builder()
+        .stdout(out.clone())
+        .stdin(
+            "fn twice(a) {\n  return a * 2;\n}\ntwice(\n21)\n\"a\nb\"\n\"${1 +\n1}\"\n".as_bytes(),
+        )
+        .build();
+
+    vm.run_repl().unwrap();
+
+    assert_eq!(
+        out.contents(),
+        "roxy:>   ...>   ...> roxy:>   ...> 42\nroxy:>   ...> a\nb\nroxy:>   ...> 2\nroxy:> Exiting...\n"
+    );
+}
+
+#[test]
+fn string_contents_outlive_the_vm() {
+    let (value, copy) = {
+        let mut vm = Vm::new();
+        let value = vm.eval("\"ro\" + \"xy\"").unwrap();
+        (value.clone(), value)
+    };
+    drop(copy);
+
+    let contents = value.as_str().unwrap();
+    assert_eq!(contents, "roxy");
+}
@@ -0,0 +1,47 @@
+use roxy::compiler::format;
+
+#[test]
+fn formats_blocks_and_operators() {
+    let source = "var   x=1 ;fn add(a,b){return a+ -b;}\n\n\n{print(add(x,2));}";
+    let expected = "\
+var x = 1;
+fn add(a, b) {
+    return a + -b;
+}
+
+{
+    print(add(x, 2));
+}
+";
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn keeps_comments() {
+    let source = "// leading\nvar a = 1; // trailing\nfn f() {\n// inside\nreturn \"${a}!\";\n}\n";
+    let expected = "\
+// leading
+var a = 1; // trailing
+fn f() {
+    // inside
+    return \"${a}!\";
+}
+";
+    assert_eq!(format(source).unwrap(), expected);
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    for source in [
+        include_str!("../programs/numbers.rx"),
+        include_str!("../programs/print.rx"),
+    ] {
+        let once = format(source).unwrap();
+        assert_eq!(format(&once).unwrap(), once);
+    }
+}
+
+#[test]
+fn rejects_invalid_code() {
+    assert!(format("var = ;").is_err());
+}
@@ -0,0 +1,96 @@
+use roxy::{error::ErrorKind, value::Value, vm::Vm};
+
+const HANDLERS: &str = "
+var offset = 1;
+
+fn onEvent(x) {
+    return x * 2 + offset;
+}
+
+fn fail(x) {
+    return -x;
+}
+";
+
+#[test]
+fn call_global_returns_the_result() {
+    let mut vm = Vm::new();
+    vm.interpret(HANDLERS).unwrap();
+
+    let value = vm.call_global("onEvent", &[Value::from(42)]).unwrap();
+    assert_eq!(value.as_int(), Some(85));
+
+    vm.set_global("offset", Value::from(0.5));
+    let value = vm.call_global("onEvent", &[Value::from(1)]).unwrap();
+    assert_eq!(value.as_float(), Some(2.5));
+}
+
+#[test]
+fn call_global_reports_missing_functions_and_arity() {
+    let mut vm = Vm::new();
+    vm.interpret(HANDLERS).unwrap();
+
+    let err = vm.call_global("missing", &[]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NameError);
+
+    let err = vm.call_global("onEvent", &[]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ArityError);
+}
+
+#[test]
+fn callee_errors_carry_a_trace() {
+    let mut vm = Vm::new();
+    vm.interpret(HANDLERS).unwrap();
+
+    let err = vm.call_global("fail", &[Value::from(true)]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+
+    let roxy::error::RxError::Runtime(e) = err else {
+        panic!("expected a runtime error");
+    };
+    let names: Vec<_> = e.trace().iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["fail", "<host>"]);
+    assert_eq!(e.line(), 9);
+
+    // the vm is still usable afterwards
+    let value = vm.call_global("fail", &[Value::from(3)]).unwrap();
+    assert_eq!(value.as_int(), Some(-3));
+}
+
+#[test]
+fn natives_can_call_back_into_scripts() {
+    let mut vm = Vm::new();
+    vm.register_native("twice", Some(1), |vm, args| {
+        let once = vm.call_global("onEvent", args)?;
+        vm.call_global("onEvent", &[once])
+    })
+    .unwrap();
+    vm.interpret(HANDLERS).unwrap();
+
+    let value = vm.eval("twice(1)").unwrap();
+    assert_eq!(value.as_int(), Some(7));
+}
+
+#[test]
+fn natives_can_eval_re_entrantly() {
+    let mut vm = Vm::new();
+    vm.register_native("eval", Some(1), |vm, args| {
+        let source = args[0].as_str().unwrap_or_default().to_owned();
+        // an inner error must leave the calling script intact
+        Ok(vm.eval(&source).unwrap_or(Value::Nil))
+    })
+    .unwrap();
+
+    let value = vm
+        .eval(
+            "fn f(a) {
+               var b = eval(\"1 + 2\");
+               var c = eval(\"1 + true\");
+               return a * 10 + b + eval(\"var inner = 4; inner\");
+             }
+             var local = 5;
+             f(local) + local",
+        )
+        .unwrap();
+    assert_eq!(value.as_int(), Some(62));
+}
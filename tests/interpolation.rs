@@ -0,0 +1,66 @@
+use roxy::{
+    error::ErrorKind,
+    vm::{Limits, Vm},
+};
+
+fn eval_str(source: &str) -> String {
+    let mut vm = Vm::new();
+    let value = vm.eval(source).unwrap();
+    value.as_str().unwrap().to_owned()
+}
+
+#[test]
+fn embedded_expressions() {
+    let source = r#"
+        var name = "roxy";
+        var count = 2;
+        "Hello ${name}, you have ${count + 1} items"
+    "#;
+    assert_eq!(eval_str(source), "Hello roxy, you have 3 items");
+}
+
+#[test]
+fn any_value_is_displayed() {
+    assert_eq!(eval_str(r#""${1.5}${true}${nil}""#), "1.5truenil");
+    assert_eq!(
+        eval_str(r#"fn f() {} "${f} ${print}""#),
+        "<fn f> <native fn print>"
+    );
+}
+
+#[test]
+fn nested_strings_and_blocks() {
+    let source = r#"
+        fn twice(x) { return x * 2; }
+        var inner = "in";
+        "a ${"b ${inner} c"} d ${twice(2)}"
+    "#;
+    assert_eq!(eval_str(source), "a b in c d 4");
+    assert_eq!(eval_str(r#""\${not} ${"x"}""#), "${not} x");
+}
+
+#[test]
+fn unclosed_interpolation() {
+    let mut vm = Vm::new();
+    let err = vm.eval(r#""a ${1 + "#).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+
+    let err = vm.eval(r#""a ${1} b"#).unwrap_err();
+    assert!(err.to_string().contains("Unterminated string"));
+}
+
+#[test]
+fn interpolation_respects_the_string_limit() {
+    let limits = Limits {
+        max_string_len: Some(8),
+        ..Default::default()
+    };
+    let mut vm = Vm::with_limits(limits);
+    assert_eq!(
+        vm.eval(r#""${1234}${5678}""#).unwrap().as_str(),
+        Some("12345678")
+    );
+
+    let err = vm.eval(r#"var s = "abcd"; "${s}${s}${s}""#).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OutOfMemory);
+}
@@ -0,0 +1,82 @@
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use roxy::{error::ErrorKind, value::Value, vm::Vm};
+
+#[test]
+fn interrupt_from_another_thread() {
+    let (running_tx, running_rx) = mpsc::channel();
+    let (resume_tx, resume_rx) = mpsc::channel::<()>();
+    let resume_rx = Mutex::new(resume_rx);
+
+    let mut vm = Vm::new();
+    let interrupt = vm.interrupt_handle();
+    vm.register_native("wait", Some(0), move |_, _| {
+        running_tx.send(()).unwrap();
+        resume_rx.lock().unwrap().recv().unwrap();
+        Ok(Value::Nil)
+    })
+    .unwrap();
+
+    let worker = thread::spawn(move || {
+        let result = vm.eval("wait(); 1 + 2").map(|_| ());
+        (vm, result)
+    });
+    running_rx.recv().unwrap();
+    interrupt.trigger();
+    resume_tx.send(()).unwrap();
+
+    let (mut vm, result) = worker.join().unwrap();
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::Interrupted);
+    assert!(!interrupt.is_triggered());
+    assert_eq!(vm.eval("1 + 2").unwrap().as_int(), Some(3));
+}
+
+#[cfg(unix)]
+#[test]
+fn sigint_is_caught_then_handed_back_to_the_host() {
+    use std::{
+        ffi::c_int,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use roxy::vm::catch_sigint;
+
+    const SIGINT: c_int = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+        fn raise(signum: c_int) -> c_int;
+    }
+
+    static HOST_SAW_SIGINT: AtomicBool = AtomicBool::new(false);
+    extern "C" fn host_handler(_: c_int) {
+        HOST_SAW_SIGINT.store(true, Ordering::SeqCst);
+    }
+
+    let handler: extern "C" fn(c_int) = host_handler;
+    // SAFETY: the handler only performs an atomic store
+    unsafe { signal(SIGINT, handler as usize) };
+
+    let mut vm = Vm::new();
+    {
+        let _guard = catch_sigint(&vm.interrupt_handle());
+        // SAFETY: a handler for SIGINT is installed
+        thread::spawn(|| unsafe { raise(SIGINT) }).join().unwrap();
+
+        let err = vm.eval("1 + 2").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert!(!HOST_SAW_SIGINT.load(Ordering::SeqCst));
+    }
+
+    // SAFETY: the host handler is back in place
+    unsafe { raise(SIGINT) };
+    assert!(HOST_SAW_SIGINT.load(Ordering::SeqCst));
+    assert_eq!(vm.eval("1 + 2").unwrap().as_int(), Some(3));
+
+    // SAFETY: restoring the default disposition is always valid
+    unsafe { signal(SIGINT, SIG_DFL) };
+}
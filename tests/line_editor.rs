@@ -0,0 +1,71 @@
+use roxy::vm::{LineEditor, ReadLine};
+
+fn read(editor: &mut LineEditor, keys: &str) -> ReadLine {
+    let mut out = Vec::new();
+    editor
+        .read_line("> ", &mut keys.as_bytes(), &mut out)
+        .unwrap()
+}
+
+fn line(text: &str) -> ReadLine {
+    ReadLine::Line(text.to_owned())
+}
+
+#[test]
+fn edits_with_cursor_keys() {
+    let mut editor = LineEditor::new(None);
+    let cases = [
+        ("1 - 3\x1b[D\x1b[D\x7f+\r", "1 + 3"),
+        ("b\x01a\x05c\r", "abc"),
+        ("ab\x1b[Hx\x1b[F\x1b[D\x1b[3~\r", "xa"),
+        ("var xs = 1\x1b[1;5D\x1b[1;5D\x17\r", "var = 1"),
+        ("print \x15q\r", "q"),
+        ("héllo\x1b[D\x1b[D\x1b[D\x1b[D\x7f\r", "éllo"),
+        ("abc\x02\x02\x0b\r", "a"),
+    ];
+
+    for (keys, expected) in cases {
+        assert_eq!(read(&mut editor, keys), line(expected), "{keys:?}");
+    }
+}
+
+#[test]
+fn control_keys_end_the_line() {
+    let mut editor = LineEditor::new(None);
+    assert_eq!(read(&mut editor, "abc\x03"), ReadLine::Interrupted);
+    assert_eq!(read(&mut editor, "\x04"), ReadLine::Eof);
+    assert_eq!(read(&mut editor, "ab\x01\x04\r"), line("b"));
+    assert_eq!(read(&mut editor, ""), ReadLine::Eof);
+    assert_eq!(read(&mut editor, "unfinished"), line("unfinished"));
+}
+
+#[test]
+fn walks_and_searches_history() {
+    let mut editor = LineEditor::new(None);
+    for entry in ["var a = 1;", "print(a);", "var b = 2;", "var b = 2;", " "] {
+        editor.add_history(entry);
+    }
+    assert_eq!(editor.history(), ["var a = 1;", "print(a);", "var b = 2;"]);
+
+    assert_eq!(read(&mut editor, "\x1b[A\x1b[A\r"), line("print(a);"));
+    assert_eq!(read(&mut editor, "x\x1b[A\x1b[B\x1b[B\r"), line("x"));
+    assert_eq!(read(&mut editor, "\x12var\r"), line("var b = 2;"));
+    assert_eq!(read(&mut editor, "\x12var\x12\r"), line("var a = 1;"));
+    assert_eq!(read(&mut editor, "\x12pr\x1b[C;\r"), line("print(a);;"));
+    assert_eq!(read(&mut editor, "keep\x12zzz\x07\r"), line("keep"));
+}
+
+#[test]
+fn persists_history() {
+    let path = std::env::temp_dir().join(format!("roxy-history-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut editor = LineEditor::new(Some(path.clone()));
+    editor.add_history("fn f() {");
+    editor.add_history("}");
+
+    let mut editor = LineEditor::new(Some(path.clone()));
+    assert_eq!(editor.history(), ["fn f() {", "}"]);
+    assert_eq!(read(&mut editor, "\x1b[A\x1b[A\r"), line("fn f() {"));
+    std::fs::remove_file(path).unwrap();
+}
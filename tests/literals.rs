@@ -0,0 +1,87 @@
+use roxy::{
+    compiler::{Span, Token, TokenType},
+    error::ErrorKind,
+    value::Value,
+    vm::Vm,
+};
+
+fn eval(source: &str) -> Value {
+    Vm::new().eval(source).unwrap()
+}
+
+#[test]
+fn identifiers() {
+    let value = eval("var x1 = 1; var snake_case = 2; var _tmp2 = 3; x1 + snake_case + _tmp2");
+    assert_eq!(value.as_int(), Some(6));
+
+    let value = eval("var größe = 2; var 名前 = 3; größe * 名前");
+    assert_eq!(value.as_int(), Some(6));
+}
+
+#[test]
+fn identifiers_follow_unicode_xid() {
+    // a combining accent continues an identifier
+    let value = eval("var e\u{301} = 2; e\u{301} * 3");
+    assert_eq!(value.as_int(), Some(6));
+
+    // alphabetic, but pattern syntax and so not XID_Start
+    let err = Vm::new().eval("var \u{2E2F} = 1;").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+}
+
+#[test]
+fn integer_literals() {
+    assert_eq!(eval("0xff").as_int(), Some(255));
+    assert_eq!(eval("0o17").as_int(), Some(15));
+    assert_eq!(eval("0b1010_1010").as_int(), Some(170));
+    assert_eq!(eval("1_000_000").as_int(), Some(1_000_000));
+    assert_eq!(eval("9223372036854775807").as_int(), Some(i64::MAX));
+}
+
+#[test]
+fn float_literals() {
+    assert_eq!(eval("1.5").as_float(), Some(1.5));
+    assert_eq!(eval("1e-9").as_float(), Some(1e-9));
+    assert_eq!(eval("2.5E+3").as_float(), Some(2500.0));
+    assert_eq!(eval("1_0.2_5").as_float(), Some(10.25));
+}
+
+#[test]
+fn bad_literals() {
+    let cases = [
+        ("9223372036854775808", "Integer literal too large."),
+        ("0xffff_ffff_ffff_ffff_f", "Integer literal too large."),
+        ("0b102", "Invalid digit for the integer base."),
+        ("0x", "Missing digits after the integer base prefix."),
+    ];
+
+    for (source, msg) in cases {
+        let err = Vm::new().eval(source).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError);
+        assert!(err.to_string().ends_with(msg), "{err}");
+    }
+}
+
+#[test]
+fn non_literal_tokens_are_not_objects() {
+    let token = Token::new(TokenType::Plus, Span(0, 1, 1));
+    let err = token.object().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert!(err.to_string().contains("Expect a literal, found '+'."));
+}
+
+#[test]
+fn too_many_constants_is_a_syntax_error() {
+    let sum = |count: i64| {
+        (0..count)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(" + ")
+    };
+
+    assert_eq!(eval(&sum(256)).as_int(), Some(255 * 256 / 2));
+
+    let err = Vm::new().eval(&sum(257)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert!(err.to_string().contains("Too many constants in one chunk."));
+}
@@ -0,0 +1,168 @@
+use roxy::{
+    error::{ErrorKind, RxError},
+    vm::{Limits, Vm},
+};
+
+fn error_line(source: &str) -> usize {
+    let mut vm = Vm::new();
+
+    match vm.interpret(source) {
+        Err(RxError::Runtime(e)) => e.line(),
+        other => panic!("expected a runtime error, got {other:?}"),
+    }
+}
+
+#[test]
+fn error_on_first_line() {
+    assert_eq!(error_line("-nil"), 1);
+}
+
+#[test]
+fn error_on_last_line() {
+    assert_eq!(error_line("1 +\n2 +\n-true"), 3);
+}
+
+#[test]
+fn error_on_middle_line() {
+    assert_eq!(error_line("1 +\n-nil\n+ 2"), 2);
+}
+
+#[test]
+fn error_after_blank_lines() {
+    assert_eq!(error_line("\n\n\n1 + -false"), 4);
+}
+
+#[test]
+fn repeated_interpretation_starts_fresh() {
+    let mut vm = Vm::new();
+
+    vm.interpret("1 + 2").unwrap();
+    vm.interpret("3 * 4").unwrap();
+
+    match vm.interpret("\n-nil") {
+        Err(RxError::Runtime(e)) => assert_eq!(e.line(), 2),
+        other => panic!("expected a runtime error, got {other:?}"),
+    }
+
+    vm.interpret("5 - 6").unwrap();
+}
+
+#[test]
+fn error_carries_stack_trace() {
+    let mut vm = Vm::new();
+
+    match vm.interpret("1 +\n-nil") {
+        Err(RxError::Runtime(e)) => {
+            let trace = e.trace();
+            assert_eq!(trace.len(), 1);
+            assert_eq!(trace[0].name, "script");
+            assert_eq!(trace[0].file, "<script>");
+            assert_eq!(trace[0].line, Some(2));
+        }
+        other => panic!("expected a runtime error, got {other:?}"),
+    }
+}
+
+#[test]
+fn errors_expose_kind_and_code() {
+    let mut vm = Vm::new();
+
+    let err = vm.interpret("1 + true").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert_eq!(err.code(), "E0200");
+
+    let err = vm.interpret("1 +").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert_eq!(err.span().map(|span| span.2), Some(1));
+}
+
+#[test]
+fn only_io_errors_have_a_source() {
+    use std::error::Error;
+
+    let mut vm = Vm::new();
+    assert!(vm.interpret("1 + true").unwrap_err().source().is_none());
+    assert!(vm.interpret("1 +").unwrap_err().source().is_none());
+
+    let err = RxError::from(std::io::Error::other("disk full"));
+    assert_eq!(err.source().unwrap().to_string(), "disk full");
+}
+
+#[test]
+fn value_stack_overflow_is_an_error() {
+    let limits = Limits {
+        // one slot holds the script itself
+        max_stack: 5,
+        ..Default::default()
+    };
+    let mut vm = Vm::with_limits(limits);
+
+    vm.interpret("1 + (2 + (3 + 4))").unwrap();
+
+    let err = vm.interpret("1 + (2 + (3 + (4 + 5)))").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::StackOverflow);
+}
+
+#[test]
+fn instruction_budget_is_enforced() {
+    let limits = Limits {
+        max_instructions: Some(4),
+        ..Default::default()
+    };
+    let mut vm = Vm::with_limits(limits);
+
+    vm.interpret("1 + 2").unwrap();
+
+    let err = vm.interpret("1 + 2 + 3").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BudgetExceeded);
+
+    // the budget applies per run
+    vm.interpret("1 + 2").unwrap();
+}
+
+#[test]
+fn interrupt_aborts_the_run() {
+    let mut vm = Vm::new();
+
+    vm.interrupt_handle().trigger();
+    let err = vm.interpret("1 + 2").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Interrupted);
+
+    vm.interpret("1 + 2").unwrap();
+}
+
+#[test]
+fn string_limits_raise_out_of_memory() {
+    let limits = Limits {
+        max_string_len: Some(8),
+        ..Default::default()
+    };
+    let mut vm = Vm::with_limits(limits);
+
+    let before = vm.stats().allocations;
+    vm.interpret("\"abcd\" + \"efgh\"").unwrap();
+    // two literals, their concatenation and the script function
+    assert_eq!(vm.stats().allocations - before, 4);
+
+    let err = vm.interpret("\"abcd\" + \"efgh\" + \"i\"").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OutOfMemory);
+}
+
+#[test]
+fn repeated_evals_stay_under_the_heap_limit() {
+    let limits = Limits {
+        max_heap: Some(4 * 1024),
+        ..Default::default()
+    };
+    let mut vm = Vm::with_limits(limits);
+    let before = vm.stats();
+
+    for _ in 0..1_000 {
+        vm.eval("\"ab\" + \"cd\"").unwrap();
+        vm.eval("1 + 2").unwrap();
+    }
+
+    let after = vm.stats();
+    assert_eq!((after.bytes, after.objects), (before.bytes, before.objects));
+    assert!(after.allocations > 5_000);
+}
@@ -0,0 +1,85 @@
+use roxy::{
+    error::{ErrorKind, RxError},
+    vm::Vm,
+};
+
+fn eval_str(source: &str) -> String {
+    let mut vm = Vm::new();
+    let value = vm.eval(source).unwrap();
+    value.as_str().unwrap().to_owned()
+}
+
+#[test]
+fn escape_sequences() {
+    assert_eq!(eval_str(r#""a\nb\t\\ \"q\" \0""#), "a\nb\t\\ \"q\" \0");
+    assert_eq!(eval_str(r#""\x41\x7f""#), "A\x7f");
+    assert_eq!(eval_str(r#""\u{1F600} \u{e9}""#), "\u{1F600} \u{e9}");
+}
+
+#[test]
+fn bad_escapes_point_at_the_escape() {
+    let mut vm = Vm::new();
+    let cases = [
+        ("\"ok \\q\"", "'\\q'", 1),
+        ("\n\"\\x8\"", "'\\x8'", 2),
+        ("\"\\xFF\"", "'\\xFF'", 1),
+        ("\"\\u{110000}\"", "'\\u{110000}'", 1),
+        ("\"\\u{}\"", "'\\u{}'", 1),
+    ];
+
+    for (source, lexeme, line) in cases {
+        let err = vm.eval(source).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::SyntaxError, "{source}");
+        assert!(err.to_string().contains(lexeme), "{err}");
+
+        let RxError::Compile(err) = err else {
+            panic!("expected a compile error");
+        };
+        let span = err.span().unwrap();
+        assert_eq!(&source[span.0..span.1], &lexeme[1..lexeme.len() - 1]);
+        assert_eq!(span.2, line);
+    }
+}
+
+#[test]
+fn raw_strings() {
+    assert_eq!(eval_str(r#"r"C:\path\n""#), r"C:\path\n");
+    assert_eq!(eval_str(r###"r#"say "hi""#"###), r#"say "hi""#);
+    assert_eq!(eval_str(r###"r##"a "# b"##"###), r##"a "# b"##);
+
+    let err = Vm::new().eval(r##"r#"open"##).unwrap_err();
+    assert!(err.to_string().contains("Unterminated raw string."));
+}
+
+#[test]
+fn triple_quoted_strings_strip_indentation() {
+    let source = "var s = \"\"\"
+        first
+          indented \\u{2713}
+
+        last
+        \"\"\";
+    s";
+    assert_eq!(eval_str(source), "first\n  indented \u{2713}\n\nlast");
+
+    assert_eq!(
+        eval_str(r#""""one "quoted" line""""#),
+        "one \"quoted\" line"
+    );
+    // only the indentation shared char by char is removed
+    assert_eq!(eval_str("\"\"\"\n\u{3000}a\n b\n\"\"\""), "\u{3000}a\n b");
+    assert_eq!(
+        eval_str("\"\"\"\n\u{3000} a\n\u{3000}\tb\n\"\"\""),
+        " a\n\tb"
+    );
+}
+
+#[test]
+fn strings_keep_line_numbers() {
+    let mut vm = Vm::new();
+    let err = vm.eval("\"\"\"\na\nb\n\"\"\";\n-nil;").unwrap_err();
+    let RxError::Runtime(err) = err else {
+        panic!("expected a runtime error");
+    };
+    assert_eq!(err.line(), 5);
+}
@@ -0,0 +1,71 @@
+use std::thread;
+
+use roxy::{value::Value, vm::Vm};
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn vms_and_values_are_send() {
+    assert_send::<Vm>();
+    assert_send::<Value>();
+}
+
+#[test]
+fn vms_run_on_worker_threads() {
+    let handles = (0..4)
+        .map(|n| {
+            let mut vm = Vm::new();
+            vm.set_global("n", Value::from(n));
+            thread::spawn(move || vm.eval("n * 10").unwrap().as_int())
+        })
+        .collect::<Vec<_>>();
+
+    let results = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(results, [Some(0), Some(10), Some(20), Some(30)]);
+}
+
+#[test]
+fn values_outlive_their_vm() {
+    let value = {
+        let mut vm = Vm::new();
+        vm.eval("fn greet() { return \"hi\"; } greet()").unwrap()
+    };
+    assert_eq!(value.as_str(), Some("hi"));
+}
+
+#[test]
+fn dropping_the_last_handle_frees_the_object() {
+    let mut vm = Vm::new();
+    let before = vm.stats();
+
+    let value = vm.eval("\"free\" + \"d\"").unwrap();
+    let copy = value.clone();
+    assert_eq!(vm.stats().objects, before.objects + 1);
+    assert!(vm.stats().bytes > before.bytes);
+
+    drop(value);
+    assert_eq!(vm.stats().objects, before.objects + 1);
+    thread::spawn(move || drop(copy)).join().unwrap();
+    assert_eq!(vm.stats().objects, before.objects);
+    assert_eq!(vm.stats().bytes, before.bytes);
+}
+
+#[test]
+fn import_copies_onto_the_target_heap() {
+    let mut source = Vm::new();
+    let value = source.eval("\"shared\"").unwrap();
+
+    let mut target = Vm::new();
+    let before = target.stats().objects;
+    let copy = thread::spawn(move || {
+        let copy = target.import(&value).unwrap();
+        (target, copy)
+    });
+    let (target, copy) = copy.join().unwrap();
+
+    assert_eq!(copy.as_str(), Some("shared"));
+    assert_eq!(target.stats().objects, before + 1);
+}
@@ -0,0 +1,49 @@
+use roxy::compiler::{tokenize, TokenType};
+
+fn roundtrip(source: &str) {
+    let joined = tokenize(source)
+        .map(|token| token.text(source))
+        .collect::<String>();
+    assert_eq!(joined, source);
+}
+
+#[test]
+fn tokens_reproduce_the_input() {
+    let sources = [
+        "",
+        "   \n\t",
+        include_str!("../programs/numbers.rx"),
+        include_str!("../programs/print.rx"),
+        "/// docs\nfn f(a, b) { return a /* inline */ + b; } // done\n",
+        "var s = \"a ${b + \"c ${d}\"} e\"; r#\"raw\"# \"\"\"\n  text\n  \"\"\"",
+        "0x_ff 1e-9 größe \u{0} @ \"unterminated",
+        "/* unterminated /* nested */",
+    ];
+
+    for source in sources {
+        roundtrip(source);
+    }
+}
+
+#[test]
+fn trivia_and_spans() {
+    let source = "var x = 1; // one\n";
+    let tokens = tokenize(source).collect::<Vec<_>>();
+
+    let kinds = tokens
+        .iter()
+        .map(|token| token.kind.is_trivia())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [false, true, false, true, false, true, false, false, true, true, true]
+    );
+
+    let comment = tokens[9];
+    assert!(matches!(comment.kind, TokenType::Comment("// one")));
+    assert_eq!((comment.span.0, comment.span.1), (11, 17));
+
+    let newline = tokens[10];
+    assert!(matches!(newline.kind, TokenType::Whitespace("\n")));
+    assert_eq!(newline.line(), 1);
+}
@@ -0,0 +1,130 @@
+use std::process::Command;
+
+use roxy::vm::{SharedBuffer, TraceFormat, Tracer, Vm};
+
+const SOURCE: &str = "fn f(a) {\n  return a * 2;\n}\nf(1) + 2";
+
+#[test]
+fn text_trace_shows_stack_and_instruction() {
+    let out = SharedBuffer::new();
+    let mut vm = Vm::builder().tracer(Tracer::new(out.clone())).build();
+    vm.eval(SOURCE).unwrap();
+
+    let trace = out.contents();
+    assert!(trace.starts_with("S: [<fn script>]\nscript:0000    1 OP_Constant         1 <fn f>\n"));
+    assert!(trace.contains("f:0002    2 OP_Multiply\n"));
+}
+
+#[test]
+fn json_trace_with_filters() {
+    let out = SharedBuffer::new();
+    let tracer = Tracer::new(out.clone())
+        .format(TraceFormat::Json)
+        .lines([2..=2])
+        .opcodes(["OP_GetLocal", "multiply"]);
+    let mut vm = Vm::builder().tracer(tracer).build();
+    vm.eval(SOURCE).unwrap();
+
+    let lines = out
+        .contents()
+        .lines()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            r#"{"fn":"f","ip":0,"line":2,"opcode":"OP_GetLocal","operands":[1],"stack":["<fn script>","<fn f>",1]}"#,
+            r#"{"fn":"f","ip":2,"line":2,"opcode":"OP_Multiply","operands":[],"stack":["<fn script>","<fn f>",1,1,2]}"#,
+        ]
+    );
+}
+
+#[test]
+fn json_strings_are_escaped() {
+    let out = SharedBuffer::new();
+    let tracer = Tracer::new(out.clone())
+        .format(TraceFormat::Json)
+        .opcodes(["Pop"]);
+    let mut vm = Vm::builder().tracer(tracer).build();
+    vm.eval("\"a\\\"b\\n\";").unwrap();
+
+    assert!(out
+        .contents()
+        .contains(r#""stack":["<fn script>","a\"b\n"]"#));
+}
+
+#[test]
+fn json_stack_values_are_typed() {
+    let out = SharedBuffer::new();
+    let tracer = Tracer::new(out.clone())
+        .format(TraceFormat::Json)
+        .opcodes(["Pop"]);
+    let mut vm = Vm::builder().tracer(tracer).build();
+    vm.eval("1; \"1\"; 1.5; 2.0; true; nil;").unwrap();
+
+    let stacks = out
+        .contents()
+        .lines()
+        .map(|line| line.split_once(r#""stack":"#).unwrap().1.to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stacks,
+        [
+            r#"["<fn script>",1]}"#,
+            r#"["<fn script>","1"]}"#,
+            r#"["<fn script>",1.5]}"#,
+            r#"["<fn script>",2.0]}"#,
+            r#"["<fn script>",true]}"#,
+            r#"["<fn script>",null]}"#,
+        ]
+    );
+}
+
+#[test]
+fn tracing_can_be_turned_off() {
+    let out = SharedBuffer::new();
+    let mut vm = Vm::builder().tracer(Tracer::new(out.clone())).build();
+    vm.set_tracer(None);
+    vm.eval(SOURCE).unwrap();
+    assert!(out.contents().is_empty());
+}
+
+#[test]
+fn cli_trace_from_env_to_file() {
+    let dir = std::env::temp_dir().join(format!("roxy-trace-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("trace.jsonl");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .env("ROXY_TRACE", "1")
+        .env("ROXY_TRACE_FORMAT", "json")
+        .arg(format!("--trace-file={}", file.display()))
+        .arg("--trace-ops=Add")
+        .arg("programs/numbers.rx")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let trace = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(trace.lines().count(), 1);
+    assert!(trace.starts_with(r#"{"fn":"script","ip":6,"line":1,"opcode":"OP_Add""#));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_rejects_unknown_trace_ops() {
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .arg("--trace-ops=Add,Ad")
+        .arg("programs/numbers.rx")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown opcode 'Ad'."));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_roxy"))
+        .env("ROXY_TRACE_OPS", "Typo")
+        .arg("programs/numbers.rx")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(64));
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use roxy::{
+    error::{ErrorKind, Runtime, RxError},
+    userdata::UserData,
+    value::Value,
+    vm::Vm,
+    Res,
+};
+
+struct Config {
+    entries: HashMap<String, i64>,
+    version: i64,
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<config v{}>", self.version)
+    }
+}
+
+impl UserData for Config {
+    fn type_name(&self) -> &str {
+        "Config"
+    }
+
+    fn get(&self, _vm: &mut Vm, name: &str) -> Res<Value> {
+        match name {
+            "version" => Ok(Value::from(self.version)),
+            _ => Err(RxError::new(Runtime::new(
+                ErrorKind::NameError,
+                &format!("Config has no property '{name}'."),
+            ))),
+        }
+    }
+
+    fn set(&mut self, _vm: &mut Vm, name: &str, value: Value) -> Res<()> {
+        self.entries
+            .insert(name.to_owned(), value.as_int().unwrap_or_default());
+        Ok(())
+    }
+
+    fn call_method(&mut self, _vm: &mut Vm, name: &str, args: &[Value]) -> Res<Value> {
+        assert_eq!(name, "lookup");
+        let key = args[0].as_str().unwrap_or_default();
+        Ok(self
+            .entries
+            .get(key)
+            .copied()
+            .map_or(Value::Nil, Value::from))
+    }
+}
+
+fn vm_with_config() -> Vm {
+    let mut vm = Vm::new();
+    let config = Config {
+        entries: HashMap::from([("retries".to_owned(), 3)]),
+        version: 2,
+    };
+    let handle = vm.new_userdata(config).unwrap();
+    vm.set_global("handle", handle);
+    vm
+}
+
+#[test]
+fn methods_and_properties_dispatch_to_rust() {
+    let mut vm = vm_with_config();
+
+    let value = vm.eval("handle.lookup(\"retries\") * 10").unwrap();
+    assert_eq!(value.as_int(), Some(30));
+
+    let value = vm.eval("handle.version").unwrap();
+    assert_eq!(value.as_int(), Some(2));
+
+    vm.eval("handle.timeout = 5;").unwrap();
+    let value = vm.eval("handle.lookup(\"timeout\")").unwrap();
+    assert_eq!(value.as_int(), Some(5));
+}
+
+#[test]
+fn userdata_reports_its_type_and_missing_members() {
+    let mut vm = vm_with_config();
+
+    let handle = vm.get_global("handle").unwrap();
+    assert_eq!(handle.get_ty(), "Config");
+    assert_eq!(handle.to_string(), "<config v2>");
+
+    let err = vm.eval("handle.missing").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NameError);
+
+    let err = vm.eval("(1).version").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+}
+
+/// Sets its flag when dropped.
+struct Tracked(Arc<AtomicBool>);
+
+impl fmt::Display for Tracked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<tracked>")
+    }
+}
+
+impl UserData for Tracked {
+    fn type_name(&self) -> &str {
+        "Tracked"
+    }
+
+    fn call_method(&mut self, vm: &mut Vm, name: &str, args: &[Value]) -> Res<Value> {
+        match name {
+            // calls back into this very object while it is borrowed
+            "outer" => match vm.get_global("tracked") {
+                Some(Value::UserData(me)) => me.get().call_method(vm, "inner", args),
+                _ => Ok(Value::Nil),
+            },
+            _ => Ok(Value::from(1)),
+        }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn unreachable_userdata_is_dropped_before_the_vm() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let mut vm = Vm::new();
+    let before = vm.stats();
+
+    let tracked = vm.new_userdata(Tracked(dropped.clone())).unwrap();
+    vm.set_global("tracked", tracked);
+    vm.eval("var copy = tracked; copy.inner()").unwrap();
+    assert!(!dropped.load(Ordering::SeqCst));
+
+    vm.eval("tracked = nil; copy = nil;").unwrap();
+    assert!(dropped.load(Ordering::SeqCst));
+    assert_eq!(vm.stats().objects, before.objects);
+    assert_eq!(vm.stats().bytes, before.bytes);
+}
+
+#[test]
+fn reentrant_calls_do_not_alias_userdata() {
+    let mut vm = Vm::new();
+    let tracked = vm
+        .new_userdata(Tracked(Arc::new(AtomicBool::new(false))))
+        .unwrap();
+    vm.set_global("tracked", tracked);
+
+    let err = vm.eval("tracked.outer()").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::TypeError);
+    assert!(
+        err.to_string().contains("Tracked is already in use."),
+        "{err}"
+    );
+    assert_eq!(vm.eval("tracked.inner()").unwrap().as_int(), Some(1));
+}